@@ -0,0 +1,73 @@
+use anyhow::{ Result };
+
+use bytes::Bytes;
+use bytestring::ByteString;
+
+use std::fmt;
+
+/// Input that is not valid UTF-8, located by byte offset and line.
+#[derive( Debug, PartialEq )]
+pub struct DecodeError {
+    offset: usize,
+    line: usize,
+}
+
+impl DecodeError {
+    /// Byte offset of the first invalid byte, from the start of the input.
+    pub fn offset( &self ) -> usize {
+        self.offset
+    }
+    /// 1-based line the invalid byte is on.
+    pub fn line( &self ) -> usize {
+        self.line
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        write!( f, "Invalid UTF-8 at byte {} (line {})", self.offset, self.line )
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Check that a framed record is UTF-8, without copying it. `offset` and
+/// `line` say where the record starts, so an error can point at the bad
+/// byte. With `lossy` set, invalid bytes become U+FFFD instead.
+pub fn decode( bytes: Bytes, offset: usize, line: usize, lossy: bool ) -> Result<ByteString> {
+    match ByteString::try_from( bytes.clone() ) {
+        Ok( text ) => Ok( text ),
+        Err( _ ) if lossy => Ok( ByteString::from( String::from_utf8_lossy( &bytes ).into_owned() ) ),
+        Err( err ) => {
+            let valid = &bytes[..err.valid_up_to()];
+            Err( DecodeError {
+                offset: offset + valid.len(),
+                line: line + memchr::memchr_iter( b'\n', valid ).count(),
+            }.into() )
+        },
+    }
+}
+
+#[cfg(test)]
+mod decode_tests {
+    use super::*;
+
+    #[test]
+    fn test_valid() {
+        let text = decode( Bytes::from( "“one” — 2 ‘three’" ), 0, 1, false ).unwrap();
+        assert_eq!( &*text, "“one” — 2 ‘three’" );
+    }
+
+    #[test]
+    fn test_invalid_byte_location() {
+        let err = decode( Bytes::from_static( b"def\ngh\xffi" ), 4, 2, false ).unwrap_err();
+        assert_eq!( err.downcast_ref::<DecodeError>(), Some( &DecodeError { offset: 10, line: 3 } ) );
+        assert_eq!( err.to_string(), "Invalid UTF-8 at byte 10 (line 3)" );
+    }
+
+    #[test]
+    fn test_lossy() {
+        let text = decode( Bytes::from_static( b"a\xffb\xc3(c" ), 0, 1, true ).unwrap();
+        assert_eq!( &*text, "a\u{FFFD}b\u{FFFD}(c" );
+    }
+}
@@ -0,0 +1,261 @@
+use flate2::read::MultiGzDecoder;
+use xz2::read::XzDecoder;
+
+use std::fs::File;
+use std::io::{ self, Cursor, Read };
+use std::path::Path;
+
+/// How many bytes of an input are looked at to tell its format.
+const MAGIC_LEN:usize = 6;
+
+/// A compressed format an input may come in, to be decompressed on the
+/// way into the framer. Line numbers and offsets are then those of the
+/// decompressed text.
+#[derive( Debug, Clone, Copy, PartialEq, Eq )]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// The format of an input starting with `prefix` and named `name`: by
+    /// its magic bytes, or failing those by its extension, so that a
+    /// damaged archive fails to decompress rather than being read as text.
+    pub fn detect( prefix: &[u8], name: &str ) -> Option<Compression> {
+        if prefix.starts_with( &[ 0x1f, 0x8b ] ) {
+            return Some( Compression::Gzip );
+        }
+        if prefix.starts_with( &[ 0x28, 0xb5, 0x2f, 0xfd ] ) {
+            return Some( Compression::Zstd );
+        }
+        if prefix.starts_with( &[ 0xfd, b'7', b'z', b'X', b'Z', 0x00 ] ) {
+            return Some( Compression::Xz );
+        }
+
+        match Path::new( name ).extension()?.to_str()? {
+            "gz" => Some( Compression::Gzip ),
+            "zst" => Some( Compression::Zstd ),
+            "xz" => Some( Compression::Xz ),
+            _ => None,
+        }
+    }
+
+    /// The format of the file at `path`, none if it is plain text. Only a
+    /// regular file is looked into, as the bytes peeked at a pipe or FIFO
+    /// would be lost to the reading after; `decompress` tells the format
+    /// of those from the bytes it reads.
+    pub fn of_file( path: &Path ) -> io::Result<Option<Compression>> {
+        if !std::fs::metadata( path )?.is_file() {
+            return Ok( None );
+        }
+        let mut prefix = Vec::with_capacity( MAGIC_LEN );
+        File::open( path )?.take( MAGIC_LEN as u64 ).read_to_end( &mut prefix )?;
+        Ok( Compression::detect( &prefix, &path.to_string_lossy() ) )
+    }
+
+    pub fn name( self ) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        }
+    }
+}
+
+/// `reader`, named `name`, decompressed as it is read if it is in one of
+/// the `Compression` formats, along with that format. This reads the
+/// first few bytes, so it blocks as reading does.
+pub fn decompress( mut reader: Box<dyn Read + Send>, name: &str ) -> io::Result<( Box<dyn Read + Send>, Option<Compression> )> {
+    let mut prefix = Vec::with_capacity( MAGIC_LEN );
+    reader.by_ref().take( MAGIC_LEN as u64 ).read_to_end( &mut prefix )?;
+    let compression = Compression::detect( &prefix, name );
+
+    let reader = Prefixed { prefix: Cursor::new( prefix ), reader, error: None };
+    let reader:Box<dyn Read + Send> = match compression {
+        None => Box::new( reader ),
+        // Archives joined end to end, as `cat a.gz b.gz` gives, are one
+        // input.
+        Some( Compression::Gzip ) => Box::new( MultiGzDecoder::new( reader ) ),
+        Some( Compression::Zstd ) => Box::new( zstd::Decoder::new( reader )? ),
+        Some( Compression::Xz ) => Box::new( XzDecoder::new_multi_decoder( reader ) ),
+    };
+    Ok( ( reader, compression ) )
+}
+
+/// A reader with the bytes already taken from its front put back. A read
+/// that has room for more than is left of them goes on into the reader,
+/// so reads come out the same size as they would have without peeking.
+struct Prefixed<R> {
+    prefix: Cursor<Vec<u8>>,
+    reader: R,
+    /// What went wrong going on into the reader, for the next read once
+    /// the rest of the prefix is handed out.
+    error: Option<io::Error>,
+}
+
+impl<R: Read> Read for Prefixed<R> {
+    fn read( &mut self, buf: &mut [u8] ) -> io::Result<usize> {
+        if let Some( err ) = self.error.take() {
+            return Err( err );
+        }
+        let taken = self.prefix.read( buf )?;
+        if taken == 0 {
+            return self.reader.read( buf );
+        }
+        if taken == buf.len() {
+            return Ok( taken );
+        }
+        match self.reader.read( &mut buf[taken..] ) {
+            Ok( read ) => Ok( taken + read ),
+            Err( err ) if err.kind() == io::ErrorKind::Interrupted => Ok( taken ),
+            Err( err ) => {
+                self.error = Some( err );
+                Ok( taken )
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod decompress_tests {
+    use super::*;
+    use crate::options::Options;
+    use crate::source::{ FileSource, InputSource };
+    use crate::test_support::{ Sum, TempPath };
+    use std::io::Write;
+    use std::sync::Arc;
+
+    const TEXT:&str = "Game 1: 3 blue, 4 red\nGame 2: 1 red\n";
+
+    fn compress( compression: Compression, text: &str ) -> Vec<u8> {
+        match compression {
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new( Vec::new(), flate2::Compression::default() );
+                encoder.write_all( text.as_bytes() ).unwrap();
+                encoder.finish().unwrap()
+            },
+            Compression::Zstd => zstd::encode_all( text.as_bytes(), 0 ).unwrap(),
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new( Vec::new(), 6 );
+                encoder.write_all( text.as_bytes() ).unwrap();
+                encoder.finish().unwrap()
+            },
+        }
+    }
+
+    fn read( input: Vec<u8>, name: &str ) -> ( io::Result<String>, Option<Compression> ) {
+        let ( mut reader, compression ) = decompress( Box::new( Cursor::new( input ) ), name ).unwrap();
+        let mut text = String::new();
+        ( reader.read_to_string( &mut text ).map( | _ | text ), compression )
+    }
+
+    #[test]
+    fn test_decompress() {
+        for compression in [ Compression::Gzip, Compression::Zstd, Compression::Xz ] {
+            // By its magic bytes, whatever the name.
+            let ( text, found ) = read( compress( compression, TEXT ), "input" );
+            assert_eq!( ( text.unwrap().as_str(), found ), ( TEXT, Some( compression ) ) );
+        }
+
+        let mut joined = compress( Compression::Gzip, "one\n" );
+        joined.extend( compress( Compression::Gzip, "two\n" ) );
+        assert_eq!( read( joined, "input.gz" ).0.unwrap(), "one\ntwo\n" );
+
+        let ( text, found ) = read( TEXT.as_bytes().to_vec(), "input" );
+        assert_eq!( ( text.unwrap().as_str(), found ), ( TEXT, None ) );
+        assert_eq!( read( b"ab".to_vec(), "input" ).0.unwrap(), "ab" );
+
+        // A damaged archive is an error rather than text.
+        let ( text, found ) = read( TEXT.as_bytes().to_vec(), "input.xz" );
+        assert_eq!( found, Some( Compression::Xz ) );
+        assert!( text.is_err() );
+    }
+
+    #[tokio::test]
+    async fn test_compressed_run() {
+        let dir = TempPath::new( "compressed" );
+        std::fs::create_dir_all( &dir ).unwrap();
+        let text = "1\n2\n\n3\nfour\n5";
+        let options = Options { quiet: true, ..Options::default() };
+        let strict = Options { strict: true, quiet: true, ..Options::default() };
+
+        let plain = dir.join( "input" );
+        std::fs::write( &plain, text ).unwrap();
+        let answer = crate::run( Sum, Arc::new( FileSource::new( &plain ) ), &options ).await.unwrap();
+        let err = crate::run( Sum, Arc::new( FileSource::new( &plain ) ), &strict ).await.unwrap_err();
+        assert!( format!( "{err:#}" ).contains( "input:5" ), "{err:#}" );
+
+        for ( compression, extension ) in [ ( Compression::Gzip, "gz" ), ( Compression::Zstd, "zst" ), ( Compression::Xz, "xz" ) ] {
+            let path = dir.join( format!( "input.{extension}" ) );
+            std::fs::write( &path, compress( compression, text ) ).unwrap();
+            let source = Arc::new( FileSource::new( &path ) );
+            assert_eq!( Compression::of_file( &path ).unwrap(), Some( compression ) );
+            assert_eq!( source.size(), None );
+
+            assert_eq!( crate::run( Sum, source.clone(), &options ).await.unwrap(), answer );
+            let mapped = Options { mapped: true, ..options.clone() };
+            assert_eq!( crate::run( Sum, source.clone(), &mapped ).await.unwrap(), answer );
+
+            // Lines are numbered in the decompressed text, as for the plain
+            // file.
+            let compressed_err = crate::run( Sum, source.clone(), &strict ).await.unwrap_err();
+            let name = format!( "input.{extension}" );
+            assert_eq!( format!( "{compressed_err:#}" ).replace( &name, "input" ), format!( "{err:#}" ) );
+        }
+
+        std::fs::write( dir.join( "damaged.gz" ), b"\x1f\x8bnot really gzip" ).unwrap();
+        let err = crate::run( Sum, Arc::new( FileSource::new( dir.join( "damaged.gz" ) ) ), &options ).await.unwrap_err();
+        assert!( format!( "{err:#}" ).contains( "damaged.gz as gzip" ), "{err:#}" );
+        assert_eq!( crate::Failure::of( &err ), crate::Failure::Io );
+    }
+
+    /// Run `Sum` over `input` written to a FIFO once, as `-f /dev/stdin`
+    /// reads a pipe.
+    #[cfg(unix)]
+    async fn through_fifo( input: Vec<u8>, options: &Options ) -> Result<usize, String> {
+        use std::time::Duration;
+
+        let path = TempPath::new( "fifo" );
+        let made = std::process::Command::new( "mkfifo" ).arg( &*path ).status().unwrap();
+        assert!( made.success() );
+
+        let writer = std::thread::spawn( {
+            let path = path.to_path_buf();
+            move || {
+                let _ = std::fs::write( &path, input );
+            }
+        } );
+        // Should the input have been lost, opening the FIFO again waits for
+        // a writer for ever, so one comes along empty-handed in time.
+        let ( done_tx, done_rx ) = std::sync::mpsc::channel::<()>();
+        let rescuer = std::thread::spawn( {
+            let path = path.to_path_buf();
+            move || {
+                if done_rx.recv_timeout( Duration::from_secs( 5 ) ).is_err() {
+                    let _ = std::fs::OpenOptions::new().write( true ).open( &path );
+                }
+            }
+        } );
+
+        let source = Arc::new( FileSource::new( path.to_path_buf() ) );
+        let answer = crate::run( Sum, source, options ).await.map_err( | err | format!( "{err:#}" ) );
+        let _ = done_tx.send( () );
+        writer.join().unwrap();
+        rescuer.join().unwrap();
+        answer
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_fifo_is_read_whole() {
+        let text = "12\n34\n56\n7\n";
+        let options = Options { quiet: true, ..Options::default() };
+        assert_eq!( through_fifo( text.as_bytes().to_vec(), &options ).await, Ok( 109 ) );
+        assert_eq!( through_fifo( compress( Compression::Gzip, text ), &options ).await, Ok( 109 ) );
+
+        // A FIFO cannot be mapped, so it is streamed even under `--mmap`.
+        let mapped = Options { mapped: true, ..options };
+        assert_eq!( through_fifo( text.as_bytes().to_vec(), &mapped ).await, Ok( 109 ) );
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{ Error };
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::Range;
+
+use crate::line::Line;
+
+/// A parse error that knows which bytes of its line are to blame, so it
+/// can be shown with a caret under them.
+pub trait Diagnostic: StdError + Send + Sync + 'static {
+    /// The bytes of the line at fault. An empty span points just before
+    /// its start, e.g. at the end of a line that stops too soon.
+    fn span( &self ) -> Range<usize>;
+
+    /// A hint at how to fix the line, such as a spelling suggestion.
+    fn help( &self ) -> Option<String> {
+        None
+    }
+}
+
+/// A `Diagnostic` as an error of its own, so that it can still be found
+/// once it has been turned into an `anyhow::Error`.
+#[derive( Debug )]
+pub struct Diagnosed( Box<dyn Diagnostic> );
+
+impl Diagnosed {
+    pub fn new( diagnostic: impl Diagnostic ) -> Diagnosed {
+        Diagnosed( Box::new( diagnostic ) )
+    }
+
+    pub fn diagnostic( &self ) -> &dyn Diagnostic {
+        self.0.as_ref()
+    }
+
+    /// The diagnostic behind `err`, if there is one.
+    pub fn find( err: &Error ) -> Option<&dyn Diagnostic> {
+        err.chain().find_map( | cause | cause.downcast_ref::<Diagnosed>() ).map( Diagnosed::diagnostic )
+    }
+}
+
+impl fmt::Display for Diagnosed {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        write!( f, "{}", self.0 )
+    }
+}
+
+impl StdError for Diagnosed {
+    fn source( &self ) -> Option<&( dyn StdError + 'static )> {
+        self.0.source()
+    }
+}
+
+/// Where `reason` points in `line`: `source:line:column` when it carries
+/// a span, `source:line` otherwise.
+pub fn location( line: &Line, reason: &Error ) -> String {
+    match Diagnosed::find( reason ) {
+        Some( diagnostic ) => line.location_at( diagnostic.span().start + 1 ),
+        None => line.location(),
+    }
+}
+
+/// Render why `line` was rejected, compiler style: the reason, where it
+/// is, and the line with a caret under the bytes at fault.
+///
+/// ```text
+/// error: unknown colour `gren`
+///  --> input:3:12
+///   |
+/// 3 | Game 3: 4 gren, 2 red
+///   |           ^^^^
+///   = help: did you mean `green`?
+/// ```
+///
+/// A reason without a span is just its location and message.
+pub fn render( line: &Line, reason: &Error ) -> String {
+    let Some( diagnostic ) = Diagnosed::find( reason ) else {
+        return format!( "{}: {reason:#}", line.location() );
+    };
+
+    let text = &*line.text;
+    let span = diagnostic.span();
+    let start = span.start.min( text.len() );
+    let end = span.end.clamp( start, text.len() );
+
+    // Only the line holding the start of the span is shown.
+    let line_start = text[..start].rfind( '\n' ).map_or( 0, | newline | newline + 1 );
+    let line_end = text[start..].find( '\n' ).map_or( text.len(), | newline | start + newline );
+    let number = line.number + text[..line_start].matches( '\n' ).count();
+
+    let gutter = number.to_string().len();
+    let indent = text[line_start..start].chars().count();
+    let carets = text[start..end.min( line_end )].chars().count().max( 1 );
+
+    let mut rendered = format!( "error: {reason:#}\n" );
+    rendered += &format!( "{:gutter$}--> {}\n", "", line.location_at( start + 1 ) );
+    rendered += &format!( "{:gutter$} |\n", "" );
+    rendered += &format!( "{number} | {}\n", &text[line_start..line_end] );
+    rendered += &format!( "{:gutter$} | {:indent$}{}", "", "", "^".repeat( carets ) );
+    if let Some( help ) = diagnostic.help() {
+        rendered += &format!( "\n{:gutter$} = help: {help}", "" );
+    }
+    rendered
+}
+
+/// The candidate `word` was most likely meant to be, if any is close.
+pub fn suggest<'a>( word: &str, candidates: &[&'a str] ) -> Option<&'a str> {
+    let word = word.to_lowercase();
+    candidates.iter()
+        .map( | candidate | ( distance( &word, candidate ), *candidate ) )
+        .filter( | ( distance, candidate ) | *distance <= candidate.len() / 2 )
+        .min()
+        .map( | ( _, candidate ) | candidate )
+}
+
+/// Levenshtein distance between `a` and `b`, in characters.
+fn distance( a: &str, b: &str ) -> usize {
+    let b:Vec<char> = b.chars().collect();
+    let mut previous:Vec<usize> = ( 0..=b.len() ).collect();
+
+    for ( i, a ) in a.chars().enumerate() {
+        let mut current = vec![ i + 1 ];
+        for ( j, b ) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from( a != *b );
+            current.push( substitute.min( previous[j + 1] + 1 ).min( current[j] + 1 ) );
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod diagnostic_tests {
+    use super::*;
+    use bytestring::ByteString;
+    use std::sync::Arc;
+
+    #[derive( Debug )]
+    struct Colour( Range<usize> );
+
+    impl fmt::Display for Colour {
+        fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+            write!( f, "unknown colour `gren`" )
+        }
+    }
+
+    impl StdError for Colour {}
+
+    impl Diagnostic for Colour {
+        fn span( &self ) -> Range<usize> {
+            self.0.clone()
+        }
+        fn help( &self ) -> Option<String> {
+            Some( "did you mean `green`?".to_string() )
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let line = Line {
+            source: Arc::from( "input" ),
+            number: 3,
+            byte_offset: 0,
+            text: ByteString::from( "Game 3: 4 gren, 2 red" ),
+        };
+        let reason = Error::from( Diagnosed::new( Colour( 10..14 ) ) );
+
+        assert_eq!( location( &line, &reason ), "input:3:11" );
+        assert_eq!( render( &line, &reason ), concat!(
+            "error: unknown colour `gren`\n",
+            " --> input:3:11\n",
+            "  |\n",
+            "3 | Game 3: 4 gren, 2 red\n",
+            "  |           ^^^^\n",
+            "  = help: did you mean `green`?",
+        ) );
+
+        let plain = anyhow::anyhow!( "No digit in line" );
+        assert_eq!( render( &line, &plain ), "input:3: No digit in line" );
+    }
+
+    #[test]
+    fn test_suggest() {
+        let colours = [ "red", "green", "blue" ];
+        assert_eq!( suggest( "gren", &colours ), Some( "green" ) );
+        assert_eq!( suggest( "Bleu", &colours ), Some( "blue" ) );
+        assert_eq!( suggest( "purple", &colours ), None );
+    }
+}
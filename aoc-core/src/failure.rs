@@ -0,0 +1,208 @@
+use anyhow::{ Error, Result };
+
+use tokio::task::JoinError;
+
+use std::any::Any;
+use std::error::Error as StdError;
+use std::fmt;
+
+use crate::decode::DecodeError;
+use crate::interrupt::Interrupted;
+use crate::known::Mismatch;
+use crate::limits::LimitError;
+use crate::number::Overflow;
+use crate::reject::Rejected;
+
+/// What kind of failure a run ended in, from least to most serious, as
+/// the most serious of several decides the exit status. The ranking is
+/// not that of the statuses: a wrong answer was at least worked out, so
+/// `Mismatch` counts for less than an input that could not be read. Each
+/// has its own exit status:
+///
+/// | Status | Failure                                                   |
+/// |--------|-----------------------------------------------------------|
+/// | 1      | `Other`: an unknown part, a bad `-p` or the like          |
+/// | 6      | `Mismatch`: an answer is not the one expected of it       |
+/// | 3      | `Io`: an input could not be opened or read                |
+/// | 4      | `Parse`: an input is not valid UTF-8, breaks its limits,  |
+/// |        | overflows the answer or, under `--strict`, has a line     |
+/// |        | that does not parse                                       |
+/// | 5      | `Internal`: a stage or worker panicked                    |
+/// | 130    | `Interrupted`: the run was stopped early, as by Ctrl-C    |
+#[derive( Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord )]
+pub enum Failure {
+    Other,
+    Mismatch,
+    Io,
+    Parse,
+    Internal,
+    Interrupted,
+}
+
+impl Failure {
+    /// The most serious failure behind `err`.
+    pub fn of( err: &Error ) -> Failure {
+        for cause in err.chain() {
+            if let Some( errors ) = cause.downcast_ref::<StageErrors>() {
+                return errors.failure();
+            }
+            if cause.is::<Interrupted>() {
+                return Failure::Interrupted;
+            }
+            if cause.is::<Mismatch>() {
+                return Failure::Mismatch;
+            }
+            if cause.is::<std::io::Error>() {
+                return Failure::Io;
+            }
+            if cause.is::<DecodeError>() || cause.is::<LimitError>() || cause.is::<Rejected>() || cause.is::<Overflow>() {
+                return Failure::Parse;
+            }
+            if cause.is::<InternalError>() {
+                return Failure::Internal;
+            }
+        }
+        Failure::Other
+    }
+
+    pub fn exit_code( self ) -> u8 {
+        match self {
+            Failure::Other => 1,
+            Failure::Io => 3,
+            Failure::Parse => 4,
+            Failure::Internal => 5,
+            Failure::Mismatch => 6,
+            Failure::Interrupted => 130,
+        }
+    }
+}
+
+/// A bug rather than bad input: a stage or worker task panicked or was
+/// cancelled.
+#[derive( Debug )]
+pub struct InternalError( String );
+
+impl InternalError {
+    pub fn new( message: impl Into<String> ) -> InternalError {
+        InternalError( message.into() )
+    }
+
+    /// A thread or task that panicked with `panic`.
+    pub fn panicked( panic: Box<dyn Any + Send> ) -> InternalError {
+        let message = panic.downcast_ref::<&str>().map( | message | message.to_string() )
+            .or_else( || panic.downcast_ref::<String>().cloned() )
+            .unwrap_or_else( || "unknown reason".to_string() );
+        InternalError( format!( "panicked: {message}" ) )
+    }
+}
+
+impl From<JoinError> for InternalError {
+    fn from( err: JoinError ) -> InternalError {
+        if !err.is_panic() {
+            return InternalError::new( "task was cancelled" );
+        }
+        InternalError::panicked( err.into_panic() )
+    }
+}
+
+impl fmt::Display for InternalError {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        write!( f, "{}", self.0 )
+    }
+}
+
+impl StdError for InternalError {}
+
+/// The errors of every stage of one run over one input, so none of them
+/// is lost behind another.
+#[derive( Debug, Default )]
+pub struct StageErrors {
+    errors: Vec<( &'static str, Error )>,
+}
+
+impl StageErrors {
+    pub fn new() -> StageErrors {
+        StageErrors::default()
+    }
+
+    /// Note how `stage` ended, handing back its value if it succeeded.
+    pub fn check<T>( &mut self, stage: &'static str, result: Result<T> ) -> Option<T> {
+        match result {
+            Ok( value ) => Some( value ),
+            Err( err ) => {
+                self.errors.push( ( stage, err ) );
+                None
+            },
+        }
+    }
+
+    /// Note how the task running `stage` ended, a panic included.
+    pub fn check_task<T>( &mut self, stage: &'static str, result: Result<Result<T>, JoinError> ) -> Option<T> {
+        let result = result.unwrap_or_else( | err | Err( InternalError::from( err ).into() ) );
+        self.check( stage, result )
+    }
+
+    /// `value` if every stage succeeded, or all of their errors.
+    pub fn finish<T>( self, value: Option<T> ) -> Result<T> {
+        match value {
+            Some( value ) if self.errors.is_empty() => Ok( value ),
+            _ => Err( self.into() ),
+        }
+    }
+
+    /// The error of every stage that failed.
+    pub fn errors( &self ) -> impl Iterator<Item = &Error> {
+        self.errors.iter().map( | ( _, err ) | err )
+    }
+
+    /// The most serious failure among the stages.
+    pub fn failure( &self ) -> Failure {
+        self.errors.iter().map( | ( _, err ) | Failure::of( err ) ).max().unwrap_or( Failure::Other )
+    }
+}
+
+impl fmt::Display for StageErrors {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        let errors:Vec<String> = self.errors.iter().map( | ( stage, err ) | format!( "{stage}: {err:#}" ) ).collect();
+        write!( f, "{}", errors.join( "; " ) )
+    }
+}
+
+impl StdError for StageErrors {}
+
+#[cfg(test)]
+mod failure_tests {
+    use super::*;
+    use anyhow::{ anyhow };
+
+    #[test]
+    fn test_failure_of() {
+        let io = Error::from( std::io::Error::other( "reset" ) ).context( "Unable to read input" );
+        assert_eq!( Failure::of( &io ), Failure::Io );
+        assert_eq!( Failure::of( &anyhow!( "No solver registered" ) ), Failure::Other );
+
+        let mut errors = StageErrors::new();
+        assert_eq!( errors.check( "reader", Err::<(), _>( io ) ), None );
+        errors.check( "workers", Err::<(), _>( InternalError::new( "panicked: oops" ).into() ) );
+        let err = errors.finish( Some( 0 ) ).unwrap_err();
+        assert_eq!( Failure::of( &err ), Failure::Internal );
+        assert_eq!( err.to_string(), "reader: Unable to read input: reset; workers: panicked: oops" );
+        assert_eq!( Failure::Internal.exit_code(), 5 );
+    }
+
+    #[test]
+    fn test_ranking() {
+        let failures = [ Failure::Other, Failure::Mismatch, Failure::Io, Failure::Parse, Failure::Internal, Failure::Interrupted ];
+        assert!( failures.windows( 2 ).all( | pair | pair[0] < pair[1] ) );
+        assert_eq!( Failure::Mismatch.max( Failure::Io ).exit_code(), 3 );
+    }
+
+    #[tokio::test]
+    async fn test_panicked_task() {
+        let task = tokio::spawn( async { panic!( "line {}", 3 ) } );
+        let mut errors = StageErrors::new();
+        assert_eq!( errors.check_task::<()>( "framer", task.await ), None );
+        assert_eq!( errors.to_string(), "framer: panicked: line 3" );
+        assert!( StageErrors::new().finish( Some( 1 ) ).is_ok() );
+    }
+}
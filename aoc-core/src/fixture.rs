@@ -0,0 +1,112 @@
+use anyhow::{ bail, Context, Result };
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+/// An example input, such as one from a puzzle statement, and the answers
+/// each part of its day should give for it.
+///
+/// Fixtures live in a directory per day, `dayN`, as `NAME.txt` with the
+/// answers beside it in `NAME.expected`: a part and its answer per line,
+/// separated by a tab. Lines starting with `#` are comments. A part
+/// without an answer there is run over the fixture but not judged.
+#[derive( Debug )]
+pub struct Fixture {
+    day: u32,
+    name: String,
+    input: PathBuf,
+    expected: BTreeMap<u32, String>,
+}
+
+impl Fixture {
+    pub fn day( &self ) -> u32 {
+        self.day
+    }
+    pub fn name( &self ) -> &str {
+        &self.name
+    }
+    pub fn input( &self ) -> &Path {
+        &self.input
+    }
+    /// The answer `part` should give, if there is one.
+    pub fn expected( &self, part: u32 ) -> Option<&str> {
+        self.expected.get( &part ).map( String::as_str )
+    }
+}
+
+/// Every fixture under `dir`, by day and then by name.
+pub fn fixtures( dir: &Path ) -> Result<Vec<Fixture>> {
+    let mut fixtures = Vec::new();
+
+    let days = fs::read_dir( dir ).context( format!( "Unable to read fixtures from {}", dir.display() ) )?;
+    for day_dir in days {
+        let day_dir = day_dir?.path();
+        let Some( day ) = day_dir.file_name().and_then( | name | name.to_str()?.strip_prefix( "day" )?.parse().ok() ) else {
+            continue;
+        };
+
+        for entry in fs::read_dir( &day_dir )? {
+            let input = entry?.path();
+            if input.extension().is_none_or( | extension | extension != "txt" ) {
+                continue;
+            }
+            let name = input.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            let expected = read_expected( &input.with_extension( "expected" ) )?;
+            fixtures.push( Fixture { day, name, input, expected } );
+        }
+    }
+
+    fixtures.sort_by( | a, b | ( a.day, &a.name ).cmp( &( b.day, &b.name ) ) );
+    Ok( fixtures )
+}
+
+/// The answers in `path`, by part, none if there is no such file.
+fn read_expected( path: &Path ) -> Result<BTreeMap<u32, String>> {
+    let mut expected = BTreeMap::new();
+    if !path.exists() {
+        return Ok( expected );
+    }
+
+    let text = fs::read_to_string( path ).context( format!( "Unable to read {}", path.display() ) )?;
+    for ( index, line ) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with( '#' ) {
+            continue;
+        }
+        let Some( ( part, answer ) ) = line.split_once( '\t' ) else {
+            bail!( "{}:{}: Expected a part and its answer separated by a tab", path.display(), index + 1 );
+        };
+        let part = part.parse().context( format!( "{}:{}: Bad part `{part}`", path.display(), index + 1 ) )?;
+        expected.insert( part, answer.to_string() );
+    }
+    Ok( expected )
+}
+
+#[cfg(test)]
+mod fixture_tests {
+    use super::*;
+    use crate::test_support::TempPath;
+
+    #[test]
+    fn test_fixtures() {
+        let dir = TempPath::new( "fixtures" );
+        fs::create_dir_all( dir.join( "day2" ) ).unwrap();
+        fs::create_dir_all( dir.join( "day10" ) ).unwrap();
+        fs::create_dir_all( dir.join( "notes" ) ).unwrap();
+        fs::write( dir.join( "day2/example.txt" ), "Game 1: 3 blue\n" ).unwrap();
+        fs::write( dir.join( "day2/example.expected" ), "# part\tanswer\n1\t1\n2\t0\n" ).unwrap();
+        fs::write( dir.join( "day10/larger.txt" ), "" ).unwrap();
+        fs::write( dir.join( "day10/README" ), "" ).unwrap();
+        fs::write( dir.join( "notes/example.txt" ), "" ).unwrap();
+
+        let fixtures = fixtures( &dir ).unwrap();
+        let found:Vec<( u32, &str )> = fixtures.iter().map( | fixture | ( fixture.day(), fixture.name() ) ).collect();
+        assert_eq!( found, vec![ ( 2, "example" ), ( 10, "larger" ) ] );
+        assert_eq!( fixtures[0].expected( 1 ), Some( "1" ) );
+        assert_eq!( fixtures[0].expected( 2 ), Some( "0" ) );
+        assert_eq!( fixtures[1].expected( 1 ), None );
+
+        fs::write( dir.join( "day2/example.expected" ), "1 1\n" ).unwrap();
+        assert!( crate::fixture::fixtures( &dir ).is_err() );
+    }
+}
@@ -0,0 +1,536 @@
+use anyhow::{ Result, anyhow, bail };
+
+use bytes::{ Bytes, BytesMut };
+use memchr::{ memchr, memchr_iter, memmem, memrchr };
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::decode::decode;
+use crate::limits::{ LimitError, Limits };
+use crate::line::Line;
+
+/// The least of the next chunk joined onto what is left of a record at a
+/// time.
+const MIN_GROWTH:usize = 64;
+
+/// How the input text is cut into the records handed to a solver.
+#[derive( Debug, Clone, PartialEq )]
+pub enum Framing {
+    /// One record per line. `\r\n` counts as a line break too, and a final
+    /// line without a trailing newline is still a record.
+    Lines,
+    /// Runs of non-blank lines separated by one or more blank lines, each
+    /// handed on with its lines joined by `\n`.
+    Paragraphs,
+    /// Records of exactly this many bytes, line breaks included; the last
+    /// one may be shorter.
+    FixedWidth( usize ),
+    /// Records separated by this string.
+    Delimiter( String ),
+}
+
+impl FromStr for Framing {
+    type Err = anyhow::Error;
+
+    /// `lines`, `paragraphs`, `fixed:N` or `delim:STRING`, where `\n`, `\r`
+    /// and `\t` in the delimiter are unescaped.
+    fn from_str( text: &str ) -> Result<Framing> {
+        match text {
+            "lines" => return Ok( Framing::Lines ),
+            "paragraphs" => return Ok( Framing::Paragraphs ),
+            _ => {},
+        }
+
+        if let Some( width ) = text.strip_prefix( "fixed:" ) {
+            let width:usize = width.parse().map_err( | err | anyhow!( "Bad record width `{width}`: {err}" ) )?;
+            if width == 0 {
+                bail!( "Record width must be at least 1" );
+            }
+            return Ok( Framing::FixedWidth( width ) );
+        }
+
+        if let Some( delimiter ) = text.strip_prefix( "delim:" ) {
+            let delimiter = delimiter.replace( "\\n", "\n" ).replace( "\\r", "\r" ).replace( "\\t", "\t" );
+            if delimiter.is_empty() {
+                bail!( "Delimiter must not be empty" );
+            }
+            return Ok( Framing::Delimiter( delimiter ) );
+        }
+
+        bail!( "Unknown framing `{text}`, expected lines, paragraphs, fixed:N or delim:STRING" )
+    }
+}
+
+/// Cuts a stream of bytes, arriving in arbitrary chunks, into records.
+///
+/// Records are handed on as slices of the chunks rather than copies, and
+/// every byte is searched only once, so framing stays linear however long
+/// the lines get. Only a record straddling two chunks is copied, along
+/// with as little of the second as it takes to find the record's end.
+pub struct Framer {
+    source: Arc<str>,
+    framing: Framing,
+    lossy: bool,
+    /// The input not yet handed on. While `next` is set, a copy of what
+    /// was left of the chunk before it and its first `appended` bytes.
+    buffer: Bytes,
+    /// A chunk that came in while part of a record was left over, still
+    /// to be joined onto it.
+    next: Option<Bytes>,
+    appended: usize,
+    /// How much of `buffer` has been searched without finding the end of
+    /// a record.
+    scanned: usize,
+    /// Byte offset of the start of `buffer` in the input.
+    offset: usize,
+    /// 1-based line the start of `buffer` is on.
+    line: usize,
+    limits: Limits,
+    /// Bytes and line breaks taken in so far, to check `max_bytes`.
+    pushed: usize,
+    pushed_lines: usize,
+    /// Records handed on so far, to check `max_lines`.
+    records: usize,
+}
+
+impl Framer {
+    /// With `lossy` set, invalid UTF-8 becomes U+FFFD instead of an error.
+    /// Input breaking any of `limits` is an error naming the line it broke
+    /// them on.
+    pub fn new( source: Arc<str>, framing: Framing, lossy: bool, limits: Limits ) -> Framer {
+        Framer {
+            source,
+            framing,
+            lossy,
+            buffer: Bytes::new(),
+            next: None,
+            appended: 0,
+            scanned: 0,
+            offset: 0,
+            line: 1,
+            limits,
+            pushed: 0,
+            pushed_lines: 0,
+            records: 0,
+        }
+    }
+
+    /// Count from byte `offset` of the input, on line `line`, for a framer
+    /// handed only the part of it from there on.
+    pub fn starting_at( mut self, offset: usize, line: usize ) -> Framer {
+        self.offset = offset;
+        self.line = line;
+        self
+    }
+
+    /// Take the next chunk of input.
+    pub fn push( &mut self, chunk: Bytes ) -> Result<()> {
+        if let Some( limit ) = self.limits.max_bytes {
+            if self.pushed + chunk.len() > limit {
+                let within = &chunk[..limit - self.pushed];
+                let line = 1 + self.pushed_lines + memchr_iter( b'\n', within ).count();
+                return Err( LimitError::Bytes { limit, line }.into() );
+            }
+            self.pushed += chunk.len();
+            self.pushed_lines += memchr_iter( b'\n', &chunk ).count();
+        }
+
+        while self.grow() {}
+        if self.buffer.is_empty() {
+            // Nothing is pending, so records are cut from the chunk itself.
+            self.buffer = chunk;
+        } else {
+            self.next = Some( chunk );
+            self.appended = 0;
+        }
+        Ok( () )
+    }
+
+    /// Join more of `next` onto the buffer, twice as much as it holds, for
+    /// the record it ends with to be looked for again. False once it is
+    /// all joined.
+    fn grow( &mut self ) -> bool {
+        let Some( next ) = &self.next else {
+            return false;
+        };
+
+        let more = self.buffer.len().max( MIN_GROWTH ).min( next.len() - self.appended );
+        let mut joined = BytesMut::with_capacity( self.buffer.len() + more );
+        joined.extend_from_slice( &self.buffer );
+        joined.extend_from_slice( &next[self.appended..self.appended + more] );
+        self.buffer = joined.freeze();
+        self.appended += more;
+        if self.appended == next.len() {
+            self.next = None;
+        }
+        true
+    }
+
+    /// The next complete record, if the input so far holds one.
+    pub fn next_record( &mut self ) -> Result<Option<Line>> {
+        let found = loop {
+            let found = match &self.framing {
+                Framing::Lines => self.find_line(),
+                Framing::Paragraphs => self.find_paragraph(),
+                Framing::FixedWidth( width ) => {
+                    ( self.buffer.len() >= *width ).then_some( ( *width, *width ) )
+                },
+                Framing::Delimiter( delimiter ) => {
+                    let delimiter = delimiter.clone();
+                    self.find_delimited( delimiter.as_bytes() )
+                },
+            };
+            if found.is_some() || !self.grow() {
+                break found;
+            }
+        };
+
+        match found {
+            Some( ( end, consumed ) ) => self.take( end, consumed ).map( Some ),
+            None => {
+                // Whatever is left is the start of a record, which must not
+                // grow past the limit while waiting for its end either. A
+                // `\r` at its end may yet turn out to be part of the line
+                // break.
+                let mut pending = self.buffer.len();
+                if matches!( self.framing, Framing::Lines | Framing::Paragraphs ) && self.buffer.ends_with( b"\r" ) {
+                    pending -= 1;
+                }
+                self.check_length( pending )?;
+                Ok( None )
+            },
+        }
+    }
+
+    /// The input has ended: hand on whatever record it left unterminated.
+    pub fn finish( &mut self ) -> Result<Option<Line>> {
+        while self.grow() {}
+        let mut end = self.buffer.len();
+
+        if self.framing == Framing::Paragraphs {
+            // The paragraph's last line may be nothing but whitespace.
+            let last_line = memrchr( b'\n', &self.buffer ).map_or( 0, | newline | newline + 1 );
+            if is_blank( &self.buffer[last_line..] ) {
+                end = last_line;
+            }
+            while end > 0 && matches!( self.buffer[end - 1], b'\n' | b'\r' ) {
+                end -= 1;
+            }
+        }
+
+        if end == 0 {
+            return Ok( None );
+        }
+
+        let consumed = self.buffer.len();
+        self.take( end, consumed ).map( Some )
+    }
+
+    /// Split the first `consumed` bytes off the buffer, handing on the
+    /// first `end` of them as a record.
+    fn take( &mut self, mut end: usize, consumed: usize ) -> Result<Line> {
+        if matches!( self.framing, Framing::Lines | Framing::Paragraphs ) && end > 0 && self.buffer[end - 1] == b'\r' {
+            end -= 1;
+        }
+
+        self.check_length( end )?;
+        self.records += 1;
+        if let Some( limit ) = self.limits.max_lines {
+            if self.records > limit {
+                return Err( LimitError::Lines { limit, line: self.line }.into() );
+            }
+        }
+
+        let consumed = self.buffer.split_to( consumed );
+        if self.next.is_some() && self.buffer.len() <= self.appended {
+            // The rest of the copy is all from the next chunk, so go back
+            // to cutting records from the chunk itself.
+            let next = self.next.take().unwrap_or_default();
+            self.buffer = next.slice( self.appended - self.buffer.len().. );
+        }
+
+        let offset = self.offset;
+        let line = self.line;
+        self.offset += consumed.len();
+        self.line += memchr_iter( b'\n', &consumed ).count();
+        self.scanned = 0;
+
+        let mut record = consumed.slice( ..end );
+
+        if self.framing == Framing::Paragraphs && memchr( b'\r', &record ).is_some() {
+            // Only a paragraph from a CRLF file has to be copied, to join
+            // its lines with plain `\n`.
+            let lines:Vec<&[u8]> = record.split( | byte | *byte == b'\n' ).map( trim_cr ).collect();
+            record = Bytes::from( lines.join( &b'\n' ) );
+        }
+
+        Ok( Line {
+            source: self.source.clone(),
+            number: line,
+            byte_offset: offset,
+            text: decode( record, offset, line, self.lossy )?,
+        } )
+    }
+
+    /// Fail if a record `length` bytes long from the start of the buffer
+    /// is over `max_line_length`.
+    fn check_length( &self, length: usize ) -> Result<()> {
+        match self.limits.max_line_length {
+            Some( limit ) if length > limit => Err( LimitError::LineLength { limit, line: self.line }.into() ),
+            _ => Ok( () ),
+        }
+    }
+
+    fn find_line( &mut self ) -> Option<( usize, usize )> {
+        match memchr( b'\n', &self.buffer[self.scanned..] ) {
+            Some( found ) => {
+                let newline = self.scanned + found;
+                Some( ( newline, newline + 1 ) )
+            },
+            None => {
+                self.scanned = self.buffer.len();
+                None
+            },
+        }
+    }
+
+    fn find_delimited( &mut self, delimiter: &[u8] ) -> Option<( usize, usize )> {
+        match memmem::find( &self.buffer[self.scanned..], delimiter ) {
+            Some( found ) => {
+                let start = self.scanned + found;
+                Some( ( start, start + delimiter.len() ) )
+            },
+            None => {
+                // The delimiter may yet straddle this chunk and the next.
+                self.scanned = self.buffer.len().saturating_sub( delimiter.len() - 1 );
+                None
+            },
+        }
+    }
+
+    /// Find the blank line closing the paragraph at the start of the
+    /// buffer, dropping any blank lines in front of it first.
+    fn find_paragraph( &mut self ) -> Option<( usize, usize )> {
+        loop {
+            let newline = self.scanned + memchr( b'\n', &self.buffer[self.scanned..] )?;
+            let line_start = self.scanned;
+            self.scanned = newline + 1;
+
+            if !is_blank( &self.buffer[line_start..newline] ) {
+                continue;
+            }
+
+            if line_start == 0 {
+                // Blank lines between paragraphs are not a record.
+                let _ = self.buffer.split_to( newline + 1 );
+                self.offset += newline + 1;
+                self.line += 1;
+                self.scanned = 0;
+                continue;
+            }
+
+            // The paragraph ends at the newline before this blank line.
+            return Some( ( line_start - 1, newline + 1 ) );
+        }
+    }
+}
+
+fn is_blank( line: &[u8] ) -> bool {
+    line.iter().all( u8::is_ascii_whitespace )
+}
+
+fn trim_cr( line: &[u8] ) -> &[u8] {
+    line.strip_suffix( b"\r" ).unwrap_or( line )
+}
+
+#[cfg(test)]
+mod frame_tests {
+    use super::*;
+
+    /// Frame `input` fed in chunks of every size, checking each gives the
+    /// same records.
+    fn frame( framing: Framing, input: &[u8] ) -> Vec<String> {
+        frame_limited( framing, Limits::default(), input ).unwrap()
+    }
+
+    /// Frame `input` under `limits` fed in chunks of every size, checking
+    /// each gives the same records or fails the same way.
+    fn frame_limited( framing: Framing, limits: Limits, input: &[u8] ) -> Result<Vec<String>, String> {
+        let mut expected:Option<Result<Vec<String>, String>> = None;
+
+        for chunk_size in 1..=input.len().max( 1 ) {
+            let mut framer = Framer::new( Arc::from( "input" ), framing.clone(), false, limits.clone() );
+            let mut frame_chunks = || -> Result<Vec<String>> {
+                let mut records = Vec::new();
+                for chunk in input.chunks( chunk_size ) {
+                    framer.push( Bytes::copy_from_slice( chunk ) )?;
+                    while let Some( record ) = framer.next_record()? {
+                        records.push( record.text.to_string() );
+                    }
+                }
+                records.extend( framer.finish()?.map( | record | record.text.to_string() ) );
+                Ok( records )
+            };
+            let records = frame_chunks().map_err( | err | err.to_string() );
+
+            if let Some( expected ) = &expected {
+                assert_eq!( &records, expected, "chunk size {chunk_size}" );
+            }
+            expected = Some( records );
+        }
+
+        expected.unwrap_or( Ok( Vec::new() ) )
+    }
+
+    #[test]
+    fn test_lines() {
+        assert_eq!( frame( Framing::Lines, b"abc\n123\n" ), [ "abc", "123" ] );
+        assert_eq!( frame( Framing::Lines, b"abc\r\n123\r\n" ), [ "abc", "123" ] );
+        assert_eq!( frame( Framing::Lines, b"abc\n\n123" ), [ "abc", "", "123" ] );
+        assert_eq!( frame( Framing::Lines, b"abc\r\n123\r" ), [ "abc", "123" ] );
+        assert!( frame( Framing::Lines, b"" ).is_empty() );
+    }
+
+    #[test]
+    fn test_split_code_points() {
+        let input = "“one” — 2 ‘three’\nfour½ 🎄\n";
+        assert_eq!( frame( Framing::Lines, input.as_bytes() ), [ "“one” — 2 ‘three’", "four½ 🎄" ] );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let input = b"abc\ndef\ngh\xffi\n";
+        for chunk_size in 1..=input.len() {
+            let mut framer = Framer::new( Arc::from( "input" ), Framing::Lines, false, Limits::default() );
+            let mut result = Ok( None );
+            for chunk in input.chunks( chunk_size ) {
+                framer.push( Bytes::copy_from_slice( chunk ) ).unwrap();
+                result = framer.next_record();
+                while let Ok( Some( _ ) ) = result {
+                    result = framer.next_record();
+                }
+                if result.is_err() {
+                    break;
+                }
+            }
+            assert_eq!( result.unwrap_err().to_string(), "Invalid UTF-8 at byte 10 (line 3)" );
+        }
+
+        let mut framer = Framer::new( Arc::from( "input" ), Framing::Lines, true, Limits::default() );
+        framer.push( Bytes::from_static( b"gh\xffi\n" ) ).unwrap();
+        assert_eq!( framer.next_record().unwrap().unwrap().text, "gh\u{FFFD}i" );
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let input = b"\nab\ncd\n\n \nef\r\n\r\ngh\r\nij";
+        assert_eq!( frame( Framing::Paragraphs, input ), [ "ab\ncd", "ef", "gh\nij" ] );
+        assert_eq!( frame( Framing::Paragraphs, b"ab\n\n" ), [ "ab" ] );
+        assert_eq!( frame( Framing::Paragraphs, b"ab\n" ), [ "ab" ] );
+        assert_eq!( frame( Framing::Paragraphs, b"ab\ncd\n  " ), [ "ab\ncd" ] );
+        assert!( frame( Framing::Paragraphs, b"\n\n" ).is_empty() );
+    }
+
+    #[test]
+    fn test_fixed_width() {
+        assert_eq!( frame( Framing::FixedWidth( 3 ), b"abcdefgh" ), [ "abc", "def", "gh" ] );
+        assert_eq!( frame( Framing::FixedWidth( 6 ), "“”½¾".as_bytes() ), [ "“”", "½¾" ] );
+    }
+
+    #[test]
+    fn test_delimiter() {
+        assert_eq!( frame( Framing::Delimiter( ", ".to_string() ), b"a, b, c" ), [ "a", "b", "c" ] );
+        assert_eq!( frame( Framing::Delimiter( "--".to_string() ), b"a--b--" ), [ "a", "b" ] );
+        assert_eq!( frame( Framing::Delimiter( "-=-".to_string() ), b"a-=b-=-c-" ), [ "a-=b", "c-" ] );
+    }
+
+    #[test]
+    fn test_line_offsets() {
+        let mut framer = Framer::new( Arc::from( "input" ), Framing::Paragraphs, false, Limits::default() );
+        framer.push( Bytes::from_static( b"\n\nab\ncd\n\nef\xff\n" ) ).unwrap();
+        let record = framer.next_record().unwrap().unwrap();
+        assert_eq!( record.text, "ab\ncd" );
+        assert_eq!( ( record.number, record.byte_offset ), ( 3, 2 ) );
+        assert_eq!( record.location(), "input:3" );
+        let err = framer.finish().unwrap_err();
+        assert_eq!( err.to_string(), "Invalid UTF-8 at byte 11 (line 6)" );
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = Limits { max_line_length: Some( 3 ), ..Limits::default() };
+        assert_eq!( frame_limited( Framing::Lines, limits.clone(), b"abc\r\nde\nfgh" ).unwrap(), [ "abc", "de", "fgh" ] );
+        // A line break's `\r` is not part of the line, even while its `\n`
+        // is still to come.
+        assert_eq!( frame_limited( Framing::Lines, limits.clone(), b"1a2\r\n3b4\r\n" ).unwrap(), [ "1a2", "3b4" ] );
+        assert_eq!( frame_limited( Framing::Lines, limits.clone(), b"abc\nde\nfghi\nj\n" ).unwrap_err(), "Line 3 is longer than 3 bytes" );
+        assert_eq!( frame_limited( Framing::Lines, limits.clone(), b"abc\nde\nfgh\r\r\n" ).unwrap_err(), "Line 3 is longer than 3 bytes" );
+        // A line still waiting for its newline is caught too.
+        let mut framer = Framer::new( Arc::from( "input" ), Framing::Lines, false, limits );
+        framer.push( Bytes::from_static( b"ab\ncdef" ) ).unwrap();
+        assert_eq!( framer.next_record().unwrap().unwrap().text, "ab" );
+        assert_eq!( framer.next_record().unwrap_err().to_string(), "Line 2 is longer than 3 bytes" );
+
+        let limits = Limits { max_lines: Some( 2 ), ..Limits::default() };
+        assert_eq!( frame_limited( Framing::Lines, limits.clone(), b"a\nb\n" ).unwrap(), [ "a", "b" ] );
+        assert_eq!( frame_limited( Framing::Lines, limits, b"a\nb\nc" ).unwrap_err(), "Line 3 is past the limit of 2 lines" );
+
+        let limits = Limits { max_bytes: Some( 4 ), ..Limits::default() };
+        assert_eq!( frame_limited( Framing::Lines, limits.clone(), b"a\nb\n" ).unwrap(), [ "a", "b" ] );
+        assert_eq!( frame_limited( Framing::Lines, limits, b"a\nb\nc" ).unwrap_err(), "Input is larger than 4 bytes at line 3" );
+    }
+
+    #[test]
+    fn test_linear_on_long_input() {
+        let line = "x".repeat( 100 ) + "\n";
+        let input = Bytes::from( line.repeat( 20_000 ) );
+
+        let mut framer = Framer::new( Arc::from( "input" ), Framing::Lines, false, Limits::default() );
+        let mut count = 0;
+        for chunk in input.chunks( 1 << 16 ) {
+            framer.push( input.slice_ref( chunk ) ).unwrap();
+            while framer.next_record().unwrap().is_some() {
+                count += 1;
+            }
+        }
+        assert!( framer.finish().unwrap().is_none() );
+        assert_eq!( count, 20_000 );
+    }
+
+    #[test]
+    fn test_records_share_chunks() {
+        let line = "x".repeat( 100 ) + "\n";
+        let input = Bytes::from( line.repeat( 50 ) );
+
+        let mut framer = Framer::new( Arc::from( "input" ), Framing::Lines, false, Limits::default() );
+        let mut copied = 0;
+        for chunk in input.chunks( 1000 ) {
+            framer.push( input.slice_ref( chunk ) ).unwrap();
+            while let Some( record ) = framer.next_record().unwrap() {
+                assert_eq!( &*record.text, &line[..100] );
+                if !input.as_ptr_range().contains( &record.text.as_ptr() ) {
+                    copied += 1;
+                }
+            }
+        }
+        assert!( framer.finish().unwrap().is_none() );
+        // Only the lines straddling the end of a chunk are copies.
+        assert_eq!( copied, 5 );
+
+        let long = format!( "{}\r\n{}\n\n{}", "a".repeat( 150 ), "b".repeat( 200 ), "c".repeat( 100 ) );
+        assert_eq!( frame( Framing::Lines, long.as_bytes() ), [ "a".repeat( 150 ), "b".repeat( 200 ), String::new(), "c".repeat( 100 ) ] );
+        assert_eq!( frame( Framing::Paragraphs, long.as_bytes() ), [ format!( "{}\n{}", "a".repeat( 150 ), "b".repeat( 200 ) ), "c".repeat( 100 ) ] );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!( "lines".parse::<Framing>().unwrap(), Framing::Lines );
+        assert_eq!( "paragraphs".parse::<Framing>().unwrap(), Framing::Paragraphs );
+        assert_eq!( "fixed:8".parse::<Framing>().unwrap(), Framing::FixedWidth( 8 ) );
+        assert_eq!( "delim:;\\n".parse::<Framing>().unwrap(), Framing::Delimiter( ";\n".to_string() ) );
+        assert!( "fixed:0".parse::<Framing>().is_err() );
+        assert!( "delim:".parse::<Framing>().is_err() );
+        assert!( "words".parse::<Framing>().is_err() );
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{ Error };
+
+use tokio::sync::watch;
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::failure::StageErrors;
+
+/// Asks a run to stop early, as Ctrl-C does. The reader stops, lines not
+/// yet handed to a worker are dropped, and the batches in flight are
+/// folded before the run ends with `Interrupted`.
+#[derive( Debug, Clone )]
+pub struct Interrupt {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl Default for Interrupt {
+    fn default() -> Interrupt {
+        Interrupt { tx: Arc::new( watch::Sender::new( false ) ) }
+    }
+}
+
+impl Interrupt {
+    pub fn new() -> Interrupt {
+        Interrupt::default()
+    }
+
+    /// An interrupt triggered by the first Ctrl-C. A second one exits at
+    /// once, for a run that will not stop.
+    pub fn on_ctrl_c() -> Interrupt {
+        let interrupt = Interrupt::new();
+        let trigger = interrupt.clone();
+        tokio::spawn( async move {
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+            eprintln!( "Interrupted, folding the lines in flight..." );
+            trigger.trigger();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit( 130 );
+            }
+        } );
+        interrupt
+    }
+
+    pub fn trigger( &self ) {
+        self.tx.send_replace( true );
+    }
+
+    pub fn is_triggered( &self ) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Wait until the interrupt is triggered.
+    pub async fn triggered( &self ) {
+        // The sender lives as long as `self`, so this cannot fail.
+        let _ = self.tx.subscribe().wait_for( | triggered | *triggered ).await;
+    }
+}
+
+/// A run that was interrupted, with what it had folded by then.
+#[derive( Debug )]
+pub struct Interrupted {
+    answer: String,
+    lines: usize,
+}
+
+impl Interrupted {
+    pub fn new( answer: impl fmt::Display, lines: usize ) -> Interrupted {
+        Interrupted { answer: answer.to_string(), lines }
+    }
+
+    /// The interrupt behind `err`, if it is one, looking into the stages
+    /// of a run as well.
+    pub fn find( err: &Error ) -> Option<&Interrupted> {
+        err.chain().find_map( | cause | {
+            if let Some( errors ) = cause.downcast_ref::<StageErrors>() {
+                return errors.errors().find_map( Interrupted::find );
+            }
+            cause.downcast_ref::<Interrupted>()
+        } )
+    }
+
+    /// The answer over the lines processed before the interrupt.
+    pub fn answer( &self ) -> &str {
+        &self.answer
+    }
+
+    pub fn lines( &self ) -> usize {
+        self.lines
+    }
+}
+
+impl fmt::Display for Interrupted {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        write!( f, "Interrupted after {} lines, with a partial answer of {}", self.lines, self.answer )
+    }
+}
+
+impl std::error::Error for Interrupted {}
@@ -0,0 +1,138 @@
+use anyhow::{ bail, Context, Result };
+
+use sha2::{ Digest, Sha256 };
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{ self, File };
+use std::io::{ self, Read };
+use std::path::{ Path, PathBuf };
+
+/// An answer that is not the one expected of it.
+#[derive( Debug )]
+pub struct Mismatch {
+    expected: String,
+    actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        write!( f, "Expected {}, got {}", self.expected, self.actual )
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// `actual` if it is the `expected` answer, a `Mismatch` otherwise.
+pub fn check( expected: &str, actual: String ) -> Result<String> {
+    if actual == expected {
+        return Ok( actual );
+    }
+    Err( Mismatch { expected: expected.to_string(), actual }.into() )
+}
+
+/// The SHA-256 of the input at `path`, in hex, telling one person's
+/// puzzle input from another's.
+pub fn input_hash( path: &Path ) -> io::Result<String> {
+    let mut file = File::open( path )?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 8192];
+    loop {
+        match file.read( &mut buffer )? {
+            0 => break,
+            size => hasher.update( &buffer[..size] ),
+        }
+    }
+    Ok( hasher.finalize().iter().map( | byte | format!( "{byte:02x}" ) ).collect() )
+}
+
+/// Answers already submitted, keyed by day, part and the hash of the
+/// input they were for. On disk, one answer per line: the day, part,
+/// input hash and answer, separated by tabs. Lines starting with `#` are
+/// comments.
+#[derive( Debug )]
+pub struct KnownAnswers {
+    path: PathBuf,
+    answers: BTreeMap<( u32, u32, String ), String>,
+}
+
+impl KnownAnswers {
+    /// The answers stored at `path`, none if there is no file yet.
+    pub fn load( path: impl Into<PathBuf> ) -> Result<KnownAnswers> {
+        let path = path.into();
+        let text = match fs::read_to_string( &path ) {
+            Ok( text ) => text,
+            Err( err ) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err( err ) => return Err( err ).context( format!( "Unable to read answers from {}", path.display() ) ),
+        };
+
+        let mut answers = BTreeMap::new();
+        for ( index, line ) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with( '#' ) {
+                continue;
+            }
+            let fields:Vec<&str> = line.split( '\t' ).collect();
+            let [ day, part, hash, answer ] = fields[..] else {
+                bail!( "{}:{}: Expected day, part, input hash and answer separated by tabs", path.display(), index + 1 );
+            };
+            let key = ( day.parse()?, part.parse()?, hash.to_string() );
+            answers.insert( key, answer.to_string() );
+        }
+
+        Ok( KnownAnswers { path, answers } )
+    }
+
+    pub fn get( &self, day: u32, part: u32, hash: &str ) -> Option<&str> {
+        self.answers.get( &( day, part, hash.to_string() ) ).map( String::as_str )
+    }
+
+    pub fn insert( &mut self, day: u32, part: u32, hash: String, answer: String ) {
+        self.answers.insert( ( day, part, hash ), answer );
+    }
+
+    /// Write every answer back to the file they were loaded from.
+    pub fn save( &self ) -> Result<()> {
+        let mut text = String::from( "# day\tpart\tinput hash\tanswer\n" );
+        for ( ( day, part, hash ), answer ) in &self.answers {
+            text += &format!( "{day}\t{part}\t{hash}\t{answer}\n" );
+        }
+        fs::write( &self.path, text ).context( format!( "Unable to write answers to {}", self.path.display() ) )
+    }
+}
+
+#[cfg(test)]
+mod known_tests {
+    use super::*;
+    use crate::Failure;
+    use crate::test_support::TempPath;
+
+    #[test]
+    fn test_check() {
+        assert_eq!( check( "142", "142".to_string() ).unwrap(), "142" );
+
+        let err = check( "142", "141".to_string() ).unwrap_err();
+        assert_eq!( err.to_string(), "Expected 142, got 141" );
+        assert_eq!( Failure::of( &err ), Failure::Mismatch );
+    }
+
+    #[test]
+    fn test_known_answers() {
+        let input = TempPath::new( "input" );
+        let path = TempPath::new( "answers" );
+        fs::write( &input, "abc" ).unwrap();
+        let hash = input_hash( &input ).unwrap();
+        assert_eq!( hash, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad" );
+
+        let mut known = KnownAnswers::load( path.to_path_buf() ).unwrap();
+        assert_eq!( known.get( 1, 1, &hash ), None );
+        known.insert( 1, 1, hash.clone(), "142".to_string() );
+        known.save().unwrap();
+
+        let known = KnownAnswers::load( path.to_path_buf() ).unwrap();
+        assert_eq!( known.get( 1, 1, &hash ), Some( "142" ) );
+        assert_eq!( known.get( 1, 2, &hash ), None );
+
+        fs::write( &path, "1\t1\t142\n" ).unwrap();
+        assert!( KnownAnswers::load( path.to_path_buf() ).is_err() );
+    }
+}
@@ -0,0 +1,196 @@
+pub mod decode;
+pub mod decompress;
+pub mod diagnostic;
+pub mod failure;
+pub mod fixture;
+pub mod frame;
+pub mod interrupt;
+pub mod known;
+pub mod limits;
+pub mod line;
+pub mod mapped;
+pub mod number;
+pub mod options;
+pub mod outcome;
+pub mod processor;
+pub mod progress;
+pub mod read;
+pub mod registry;
+pub mod reject;
+pub mod solver;
+pub mod source;
+#[cfg(test)]
+mod test_support;
+pub mod timings;
+
+pub use failure::Failure;
+pub use frame::Framing;
+pub use limits::Limits;
+pub use line::Line;
+pub use options::Options;
+pub use outcome::Outcome;
+pub use registry::{ Params, Registry };
+pub use solver::Solver;
+pub use source::InputSource;
+
+use anyhow::{ Context, Result };
+
+use bytes::Bytes;
+
+use tokio::join;
+
+use tokio::sync::mpsc::{ channel };
+
+use tracing::{ Instrument, Span, info_span };
+
+use std::io::{ self, IsTerminal };
+use std::sync::Arc;
+
+use crate::decompress::Compression;
+use crate::failure::StageErrors;
+use crate::progress::Progress;
+
+/// The rendered answer for each source of a run, and the answer for all
+/// of them together.
+pub struct Totals<A> {
+    pub per_source: Vec<(String, String)>,
+    pub total: A,
+}
+
+/// Run `solver` over `source`: read → chunk into lines → process every
+/// line → fold the contributions into the answer.
+pub async fn run<S: Solver>( solver: S, source: Arc<dyn InputSource>, options: &Options ) -> Result<S::Answer> {
+    run_source( Arc::new( solver ), source, options ).await
+}
+
+/// Run `solver` over every source in turn, answering for each of them and
+/// merging those answers into a grand total.
+pub async fn run_sources<S: Solver>( solver: S, sources: &[Arc<dyn InputSource>], options: &Options ) -> Result<Totals<S::Answer>> {
+    let solver = Arc::new( solver );
+
+    let mut per_source = Vec::new();
+    let mut total = solver.initial();
+
+    for source in sources {
+        let answer = run_source( solver.clone(), source.clone(), options ).await?;
+        per_source.push( ( source.name(), answer.to_string() ) );
+        total = solver.merge( total, answer ).context( format!( "Unable to add {} to the total", source.name() ) )?;
+    }
+
+    Ok( Totals { per_source, total } )
+}
+
+async fn run_source<S: Solver>( solver: Arc<S>, source: Arc<dyn InputSource>, options: &Options ) -> Result<S::Answer> {
+    let framing = options.framing.clone().unwrap_or_else( || solver.framing() );
+
+    let progress = Arc::new( Progress::new( source.size() ) );
+    let display = ( options.progress && io::stderr().is_terminal() ).then( || tokio::spawn( progress::display( progress.clone() ) ) );
+
+    let span = info_span!( "run", source = %source.name() );
+
+    // Only a regular file can be mapped, and a compressed one has to be
+    // read through from the start. A pipe or FIFO is not looked into at
+    // all, which would lose what was read of it.
+    let mapped = source.path()
+        .filter( | _ | options.mapped && mapped::can_split( &framing, options ) )
+        .filter( | path | std::fs::metadata( path ).is_ok_and( | metadata | metadata.is_file() ) )
+        .filter( | path | matches!( Compression::of_file( path ), Ok( None ) ) );
+    let answer = match mapped {
+        Some( path ) => {
+            mapped::run_mapped( solver, path.to_path_buf(), Arc::from( source.name() ), options.clone(), progress.clone() )
+                .instrument( span ).await
+        },
+        None => stream( solver, source, framing, options, progress.clone(), span ).await,
+    };
+
+    progress.finish();
+    if let Some( timings ) = &options.timings {
+        timings.record_run( &progress );
+    }
+    if let Some( display ) = display {
+        // The display only draws, so there is nothing to report if it
+        // went wrong.
+        let _ = display.await;
+    }
+
+    answer
+}
+
+/// Run `solver` over `source` as it is read, one stage after another.
+async fn stream<S: Solver>( solver: Arc<S>, source: Arc<dyn InputSource>, framing: Framing, options: &Options, progress: Arc<Progress>, span: Span ) -> Result<S::Answer> {
+    let capacity = options.capacity().get();
+    let (tx_chunk,rx_chunk) = channel::<Bytes>( capacity );
+    let (tx_line,rx_line) = channel::<Line>( capacity );
+
+    // Each stage has a span of its own within the run's. An interrupt
+    // stops the reader, and so the framer once it has framed what was
+    // read.
+    let input_future = async {
+        tokio::select! {
+            biased;
+            input = read::read_input( source.clone(), tx_chunk, progress.clone(), options.buffer_size() ) => input,
+            _ = options.interrupt.triggered() => Ok( () ),
+        }
+    }.instrument( info_span!( parent: &span, "reader" ) );
+    let framer = frame::Framer::new( Arc::from( source.name() ), framing, options.lossy, options.limits.clone() );
+    let chunker_future = tokio::spawn(
+        processor::chunker( rx_chunk, tx_line, framer, progress.clone() ).instrument( info_span!( parent: &span, "framer" ) )
+    );
+    let distribute_work_future = tokio::spawn(
+        processor::distribute_work( solver, rx_line, options.clone(), progress.clone() ).instrument( info_span!( parent: &span, "reducer" ) )
+    );
+
+    let ( input, chunker, answer ) = join!( input_future, chunker_future, distribute_work_future );
+
+    let mut errors = StageErrors::new();
+    errors.check( "reader", input );
+    errors.check_task( "framer", chunker );
+    let folded = errors.check_task( "workers", answer );
+    // Lines that stopped coming because the reader or framer failed are
+    // not the end of the input, and so not worth a summary.
+    Ok( errors.finish( folded )?.summarise( options ) )
+}
+
+#[cfg(test)]
+mod run_tests {
+    use super::*;
+    use crate::source::Memory;
+    use crate::test_support::Sum;
+
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+
+    /// Every stage shares the one thread, so any of them blocking it, or
+    /// waiting on a full channel nobody drains, would hang the run.
+    #[tokio::test( flavor = "current_thread" )]
+    async fn test_current_thread_completes() {
+        let input = "1\n".repeat( 10_000 );
+        let source = Arc::new( Memory::new( "input", &input ) );
+        let options = Options {
+            capacity: Some( NonZeroUsize::MIN ),
+            jobs: NonZeroUsize::new( 2 ),
+            ..Options::default()
+        };
+
+        let run = run( Sum, source, &options );
+        let answer = tokio::time::timeout( Duration::from_secs( 10 ), run ).await;
+        assert_eq!( answer.expect( "pipeline stalled" ).unwrap(), 10_000 );
+    }
+
+    #[tokio::test]
+    async fn test_failures_reach_the_caller() {
+        let source = Arc::new( Memory::new( "input", "1\n\u{FFFD}\n" ) );
+        let bad = Arc::new( crate::source::FileSource::new( "does-not-exist" ) );
+        let options = Options {
+            limits: crate::Limits { max_lines: Some( 1 ), ..crate::Limits::default() },
+            ..Options::default()
+        };
+
+        let err = run( Sum, source, &options ).await.unwrap_err();
+        assert_eq!( Failure::of( &err ), Failure::Parse );
+        assert_eq!( err.to_string(), "framer: Line 2 is past the limit of 1 lines" );
+
+        let err = run( Sum, bad, &Options::default() ).await.unwrap_err();
+        assert_eq!( Failure::of( &err ), Failure::Io );
+    }
+}
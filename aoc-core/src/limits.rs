@@ -0,0 +1,45 @@
+use std::fmt;
+
+/// Bounds on the input, for running solvers over files from elsewhere.
+/// Every limit is off unless set.
+#[derive( Debug, Clone, Default )]
+pub struct Limits {
+    /// Longest record, in bytes. With line framing that is the longest
+    /// line; a record still waiting for its end counts too.
+    pub max_line_length: Option<usize>,
+    /// Most records in the input.
+    pub max_lines: Option<usize>,
+    /// Most bytes in the input.
+    pub max_bytes: Option<usize>,
+}
+
+/// The input broke one of its `Limits`.
+#[derive( Debug, PartialEq )]
+pub enum LimitError {
+    LineLength { limit: usize, line: usize },
+    Lines { limit: usize, line: usize },
+    Bytes { limit: usize, line: usize },
+}
+
+impl LimitError {
+    /// 1-based line the limit was broken on.
+    pub fn line( &self ) -> usize {
+        match self {
+            LimitError::LineLength { line, .. } => *line,
+            LimitError::Lines { line, .. } => *line,
+            LimitError::Bytes { line, .. } => *line,
+        }
+    }
+}
+
+impl fmt::Display for LimitError {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        match self {
+            LimitError::LineLength { limit, line } => write!( f, "Line {line} is longer than {limit} bytes" ),
+            LimitError::Lines { limit, line } => write!( f, "Line {line} is past the limit of {limit} lines" ),
+            LimitError::Bytes { limit, line } => write!( f, "Input is larger than {limit} bytes at line {line}" ),
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
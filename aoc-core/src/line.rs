@@ -0,0 +1,55 @@
+use bytestring::ByteString;
+
+use std::sync::Arc;
+
+/// A framed record, with where it came from so that anything said about it
+/// can point back into the input.
+#[derive( Debug, Clone, PartialEq )]
+pub struct Line {
+    /// Name of the input, as given by its `InputSource`.
+    pub source: Arc<str>,
+    /// 1-based line the record starts on.
+    pub number: usize,
+    /// Byte offset of the start of the record in the input.
+    pub byte_offset: usize,
+    pub text: ByteString,
+}
+
+impl Line {
+    /// `source:line`, e.g. `input:37`.
+    pub fn location( &self ) -> String {
+        format!( "{}:{}", self.source, self.number )
+    }
+
+    /// `source:line:column` for a 1-based byte `column` within the
+    /// record, e.g. `input:37:12`. A column past a line break in the record
+    /// lands on the line it is on.
+    pub fn location_at( &self, column: usize ) -> String {
+        let before = &self.text.as_bytes()[..column.saturating_sub( 1 ).min( self.text.len() )];
+        match memchr::memrchr( b'\n', before ) {
+            Some( newline ) => {
+                let number = self.number + memchr::memchr_iter( b'\n', before ).count();
+                format!( "{}:{}:{}", self.source, number, column - newline - 1 )
+            },
+            None => format!( "{}:{}:{}", self.source, self.number, column ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod line_tests {
+    use super::*;
+
+    #[test]
+    fn test_location() {
+        let line = Line {
+            source: Arc::from( "input" ),
+            number: 37,
+            byte_offset: 512,
+            text: ByteString::from( "Game 1: 3 blue\nGame 2: 4 red" ),
+        };
+        assert_eq!( line.location(), "input:37" );
+        assert_eq!( line.location_at( 12 ), "input:37:12" );
+        assert_eq!( line.location_at( 20 ), "input:38:5" );
+    }
+}
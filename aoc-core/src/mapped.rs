@@ -0,0 +1,431 @@
+use anyhow::{ Context, Error, Result };
+
+use bytes::Bytes;
+use memchr::{ memchr, memchr_iter };
+use memmap2::Mmap;
+
+use std::fs::File;
+use std::ops::Range;
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::thread;
+use std::time::{ Duration, Instant };
+
+use tracing::{ Span, debug, debug_span, info };
+
+use crate::failure::{ InternalError, StageErrors };
+use crate::frame::{ Framer, Framing };
+use crate::interrupt::Interrupted;
+use crate::number::Overflow;
+use crate::options::Options;
+use crate::processor::{ self, BATCH_SIZE, Skipped };
+use crate::progress::{ Progress, Stage };
+use crate::solver::Solver;
+use crate::timings::{ Slowest, Timings };
+
+/// Whether input framed by `framing` can be split into ranges under
+/// `options`. Only lines can be cut apart at any newline, and per-line
+/// output, `max_lines` and `max_bytes` all need the input as a whole, so
+/// anything else is streamed as usual.
+pub fn can_split( framing: &Framing, options: &Options ) -> bool {
+    *framing == Framing::Lines
+        && options.lines.is_none()
+        && options.limits.max_lines.is_none()
+        && options.limits.max_bytes.is_none()
+}
+
+/// Run `solver` over the file at `path` mapped into memory: cut it into a
+/// range of whole lines per job, frame and process each range on a thread
+/// of its own, and merge the answers of the ranges in input order.
+///
+/// Lines are numbered, skipped and rejected just as when the file is
+/// streamed, and the answer, or the error, is the same.
+pub async fn run_mapped<S: Solver>( solver: Arc<S>, path: PathBuf, name: Arc<str>, options: Options, progress: Arc<Progress> ) -> Result<S::Answer> {
+    let span = Span::current();
+    let ranges = tokio::task::spawn_blocking( move || {
+        let _entered = span.enter();
+        run_ranges( solver.as_ref(), &path, name, &options, &progress )
+    } ).await;
+
+    let mut errors = StageErrors::new();
+    let answer = match ranges {
+        Ok( Ok( answer ) ) => Some( answer ),
+        Ok( Err( ( stage, err ) ) ) => errors.check( stage, Err( err ) ),
+        Err( err ) => errors.check_task( "workers", Err( err ) ),
+    };
+    errors.finish( answer )
+}
+
+/// An error along with the stage of a streamed run that would have run
+/// into it, for it to be reported the same way.
+type StageError = ( &'static str, Error );
+
+fn in_stage( stage: &'static str ) -> impl FnOnce( Error ) -> StageError {
+    move | err | ( stage, err )
+}
+
+/// What came of one range.
+struct Part<A> {
+    answer: A,
+    /// Lines processed, blank ones aside.
+    lines: usize,
+    skipped: Skipped,
+    /// Whether the range was left before its end.
+    interrupted: bool,
+}
+
+fn run_ranges<S: Solver>( solver: &S, path: &Path, name: Arc<str>, options: &Options, progress: &Progress ) -> Result<S::Answer, StageError> {
+    let started = Instant::now();
+    let file = File::open( path ).context( format!( "Unable to open {name}" ) ).map_err( in_stage( "reader" ) )?;
+    // SAFETY: changing the file while it is mapped is undefined behaviour.
+    // As with any tool that maps its input, nobody is trusted to do so
+    // during a run.
+    let map = unsafe { Mmap::map( &file ) }.context( format!( "Unable to map {name}" ) ).map_err( in_stage( "reader" ) )?;
+    let input = Bytes::from_owner( map );
+    progress.spent( Stage::Reader, started.elapsed() );
+
+    let ranges = split( &input, options.jobs().get() );
+    debug!( ranges = ranges.len(), bytes = input.len(), "split the input" );
+
+    // The lines of each range are numbered on from those before it.
+    let newlines = thread::scope( | scope | {
+        let counts:Vec<_> = ranges.iter()
+            .map( | range | scope.spawn( || memchr_iter( b'\n', &input[range.clone()] ).count() ) )
+            .collect();
+        counts.into_iter().map( | count | count.join().map_err( InternalError::panicked ) ).collect::<Result<Vec<usize>, _>>()
+    } ).map_err( | err | ( "reader", err.into() ) )?;
+    let mut line = 1;
+    let first_lines:Vec<usize> = newlines.iter().map( | count | {
+        line += count;
+        line - count
+    } ).collect();
+
+    let failed = AtomicUsize::new( usize::MAX );
+    let span = Span::current();
+    let starts:Vec<String> = first_lines.iter().map( | line | format!( "{name}:{line}" ) ).collect();
+    let parts:Vec<Result<Part<S::Answer>, StageError>> = thread::scope( | scope | {
+        let threads:Vec<_> = ranges.iter().zip( &first_lines ).zip( &starts ).enumerate().map( | ( index, ( ( range, line ), start ) ) | {
+            let framer = Framer::new( name.clone(), Framing::Lines, options.lossy, options.limits.clone() ).starting_at( range.start, *line );
+            let input = input.slice( range.clone() );
+            let ( stop, span ) = ( Stop { failed: &failed, range: index }, &span );
+            scope.spawn( move || {
+                let _entered = debug_span!( parent: span, "range", %start ).entered();
+                let part = run_range( solver, framer, range.start, input, options, progress, &stop );
+                if part.is_err() {
+                    stop.fail();
+                }
+                part
+            } )
+        } ).collect();
+
+        threads.into_iter().zip( &starts ).map( | ( thread, start ) | {
+            thread.join().unwrap_or_else( | panic | {
+                Err( ( "workers", InternalError::new( format!( "range from {start} {}", InternalError::panicked( panic ) ) ).into() ) )
+            } )
+        } ).collect()
+    } );
+
+    let started = Instant::now();
+    let mut answer = solver.initial();
+    let mut lines = 0;
+    let mut skipped = Skipped::default();
+    let mut interrupted = false;
+    for ( part, range ) in parts.into_iter().zip( &ranges ) {
+        let part = part.inspect_err( | ( _, err ) | processor::show_rejected( err, options ) )?;
+        answer = match solver.merge( answer, part.answer ) {
+            Ok( answer ) => answer,
+            Err( err ) => {
+                let err = refold( solver, name, options, input.slice( ..range.end ) )
+                    .unwrap_or_else( || err.context( "Unable to add up the ranges" ) );
+                return Err( ( "workers", err ) );
+            },
+        };
+        lines += part.lines;
+        skipped.merge( part.skipped );
+        interrupted |= part.interrupted;
+    }
+    progress.spent( Stage::Reducer, started.elapsed() );
+    debug!( lines, ranges = ranges.len(), "folded every range" );
+
+    if interrupted {
+        info!( lines, "interrupted" );
+        if let Some( rejects ) = &options.rejects {
+            rejects.flush().map_err( in_stage( "workers" ) )?;
+        }
+        return Err( ( "workers", Interrupted::new( answer, lines ).into() ) );
+    }
+
+    processor::summarise( options, &skipped, lines ).map_err( in_stage( "workers" ) )?;
+    Ok( answer )
+}
+
+/// Fold every line of `input`, from its start, one at a time, to find the
+/// line the answer failed on where merging the answers of its ranges did,
+/// and name it as streaming would. None if no line fails so.
+fn refold<S: Solver>( solver: &S, name: Arc<str>, options: &Options, input: Bytes ) -> Option<Error> {
+    let mut framer = Framer::new( name, Framing::Lines, options.lossy, options.limits.clone() );
+    framer.push( input ).ok()?;
+
+    let mut answer = solver.initial();
+    loop {
+        let line = match framer.next_record().ok()? {
+            Some( line ) => line,
+            None => framer.finish().ok()??,
+        };
+        // Lines that do not parse were skipped, as they are again.
+        if let Ok( Some( contribution ) ) = solver.parse( &line.text ).and_then( | record | solver.contribution( record ) ) {
+            answer = match solver.fold( answer, contribution ) {
+                Ok( answer ) => answer,
+                Err( reason ) => return Some( reason.context( line.location() ) ),
+            };
+        }
+    }
+}
+
+/// Which ranges are to stop early: those after the first to fail, as its
+/// error ends the run. Those before it run on, for one of them may yet
+/// fail on an earlier line, which is the one streaming would report.
+struct Stop<'a> {
+    /// The index of the first range to fail so far.
+    failed: &'a AtomicUsize,
+    range: usize,
+}
+
+impl Stop<'_> {
+    fn is_set( &self ) -> bool {
+        self.failed.load( Ordering::Relaxed ) < self.range
+    }
+
+    fn fail( &self ) {
+        self.failed.fetch_min( self.range, Ordering::Relaxed );
+    }
+}
+
+/// Frame and process one range, `offset` bytes into the input and handed
+/// to `framer` whole, so that its lines are slices of the map, folding
+/// them into an answer of its own.
+fn run_range<S: Solver>( solver: &S, mut framer: Framer, offset: usize, input: Bytes, options: &Options, progress: &Progress, stop: &Stop ) -> Result<Part<S::Answer>, StageError> {
+    let started = Instant::now();
+    let timed = options.timings.is_some();
+    let mut slowest = Slowest::new( options.timings.as_ref().map_or( 0, Timings::limit ) );
+    let mut part = Part { answer: solver.initial(), lines: 0, skipped: Skipped::default(), interrupted: false };
+
+    // Progress is told about whole batches of lines, as when streaming.
+    let mut batch = 0;
+    let mut reported = offset;
+    let length = input.len();
+    framer.push( input ).map_err( in_stage( "framer" ) )?;
+
+    let mut framing = Duration::ZERO;
+    let end = loop {
+        let framing_started = Instant::now();
+        let line = match framer.next_record().map_err( in_stage( "framer" ) )? {
+            Some( line ) => line,
+            None => match framer.finish().map_err( in_stage( "framer" ) )? {
+                Some( line ) => line,
+                None => break None,
+            },
+        };
+        framing += framing_started.elapsed();
+
+        if batch == BATCH_SIZE {
+            progress.framed( batch );
+            progress.processed( batch );
+            progress.read( line.byte_offset - reported );
+            reported = line.byte_offset;
+            batch = 0;
+            if options.interrupt.is_triggered() || stop.is_set() {
+                part.interrupted = true;
+                break Some( line.byte_offset );
+            }
+        }
+        batch += 1;
+
+        // A blank line holds no record, so it is not worth a reject.
+        if line.text.trim().is_empty() {
+            continue;
+        }
+        part.lines += 1;
+
+        let line_started = timed.then( Instant::now );
+        let contribution = solver.parse( &line.text ).and_then( | record | solver.contribution( record ) );
+        if let Some( line_started ) = line_started {
+            slowest.note( &line, line_started.elapsed() );
+        }
+
+        match contribution {
+            Ok( Some( contribution ) ) => {
+                part.answer = solver.fold( part.answer, contribution ).context( line.location() ).map_err( in_stage( "workers" ) )?;
+            },
+            Ok( None ) => {},
+            Err( reason ) if reason.chain().any( | cause | cause.is::<Overflow>() ) => {
+                return Err( ( "workers", reason.context( line.location() ) ) );
+            },
+            Err( reason ) => processor::skip( &line, &reason, options, &mut part.skipped ).map_err( in_stage( "workers" ) )?,
+        }
+    };
+
+    progress.framed( batch );
+    progress.processed( batch );
+    progress.read( end.unwrap_or( offset + length ) - reported );
+    progress.spent( Stage::Framer, framing );
+    progress.spent( Stage::Workers, started.elapsed().saturating_sub( framing ) );
+    if let Some( timings ) = &options.timings {
+        timings.record_lines( slowest );
+    }
+
+    Ok( part )
+}
+
+/// Cut `input` into at most `ranges` ranges of about the same length, each
+/// ending just after a newline or at the end of the input.
+fn split( input: &[u8], ranges: usize ) -> Vec<Range<usize>> {
+    let step = input.len().div_ceil( ranges.max( 1 ) ).max( 1 );
+
+    let mut split = Vec::new();
+    let mut start = 0;
+    while start < input.len() {
+        let target = ( start + step ).min( input.len() );
+        // Looking from just before the target keeps a newline right there.
+        let end = match memchr( b'\n', &input[target - 1..] ) {
+            Some( found ) => target + found,
+            None => input.len(),
+        };
+        split.push( start..end );
+        start = end;
+    }
+    split
+}
+
+#[cfg(test)]
+mod mapped_tests {
+    use super::*;
+    use crate::limits::Limits;
+    use crate::reject::Rejects;
+    use crate::source::FileSource;
+    use crate::test_support::{ Sum, TempPath };
+    use std::fs;
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn test_split() {
+        let input = b"one\ntwo\nthree\n\nfour";
+        for ranges in 1..=input.len() + 1 {
+            let split = split( input, ranges );
+            assert!( split.len() <= ranges, "{ranges} ranges" );
+            assert_eq!( split[0].start, 0 );
+            assert_eq!( split[split.len() - 1].end, input.len() );
+            for pair in split.windows( 2 ) {
+                assert_eq!( pair[0].end, pair[1].start );
+                assert_eq!( input[pair[0].end - 1], b'\n' );
+            }
+        }
+        assert_eq!( split( input, 2 ), [ 0..14, 14..19 ] );
+        assert!( split( b"", 4 ).is_empty() );
+    }
+
+    #[test]
+    fn test_lines_share_map() {
+        let path = TempPath::new( "mapped-share" );
+        let text:String = ( 1..1000 ).map( | number | format!( "{number}\n" ) ).collect();
+        fs::write( &path, &text ).unwrap();
+        let input = Bytes::from_owner( unsafe { Mmap::map( &File::open( &path ).unwrap() ) }.unwrap() );
+        let map = input.as_ptr_range();
+
+        let mut lines = 0;
+        for range in split( &input, 3 ) {
+            let mut framer = Framer::new( Arc::from( "input" ), Framing::Lines, false, Limits::default() ).starting_at( range.start, 1 );
+            framer.push( input.slice( range ) ).unwrap();
+            while let Some( line ) = framer.next_record().unwrap() {
+                assert_eq!( &input[line.byte_offset..line.byte_offset + line.text.len()], line.text.as_bytes() );
+                assert!( map.contains( &line.text.as_ptr() ), "line {} was copied", line.text );
+                lines += 1;
+            }
+        }
+        assert_eq!( lines, 999 );
+    }
+
+    #[tokio::test]
+    async fn test_matches_streaming() {
+        let path = TempPath::new( "mapped" );
+        let mut text = String::new();
+        for number in 1..5000 {
+            text += &match number % 97 {
+                0 => format!( "x{number}\n" ),
+                1 => "\n".to_string(),
+                2 => format!( "{number}\r\n" ),
+                _ => format!( "{number}\n" ),
+            };
+        }
+        text += "12345";
+        fs::write( &path, &text ).unwrap();
+        let source = Arc::new( FileSource::new( path.to_path_buf() ) );
+
+        let streamed = crate::run( Sum, source.clone(), &Options { quiet: true, ..Options::default() } ).await.unwrap();
+        for jobs in [ 1, 2, 3, 7, 64 ] {
+            let options = Options { mapped: true, jobs: NonZeroUsize::new( jobs ), quiet: true, ..Options::default() };
+            assert_eq!( crate::run( Sum, source.clone(), &options ).await.unwrap(), streamed, "{jobs} jobs" );
+        }
+
+        // Lines are numbered across ranges as they are in the file, where
+        // line N is N.
+        let rejects = TempPath::new( "mapped-rejects" );
+        let options = Options {
+            mapped: true,
+            jobs: NonZeroUsize::new( 4 ),
+            rejects: Some( Rejects::create( &rejects ).unwrap() ),
+            quiet: true,
+            ..Options::default()
+        };
+        assert_eq!( crate::run( Sum, source.clone(), &options ).await.unwrap(), streamed );
+        let rejected = fs::read_to_string( &rejects ).unwrap();
+        assert_eq!( rejected.lines().count(), 51 );
+        for reject in rejected.lines() {
+            let fields:Vec<&str> = reject.split( '\t' ).collect();
+            let line = format!( "{}:{}", path.display(), &fields[fields.len() - 1][1..] );
+            assert!( fields[0] == line || fields[0].starts_with( &format!( "{line}:" ) ), "{reject}" );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_strict_matches_streaming() {
+        let path = TempPath::new( "mapped-strict" );
+        // Every range but the first fails on its first line, and the first
+        // on a line well into it.
+        let text:String = ( 1..20_000 ).map( | number | if number < 4000 { format!( "{number}\n" ) } else { "x\n".to_string() } ).collect();
+        fs::write( &path, &text ).unwrap();
+        let source = Arc::new( FileSource::new( path.to_path_buf() ) );
+
+        let strict = Options { strict: true, quiet: true, ..Options::default() };
+        let streamed = crate::run( Sum, source.clone(), &strict ).await.unwrap_err();
+        assert!( format!( "{streamed:#}" ).contains( ":4000" ), "{streamed:#}" );
+        for jobs in [ 2, 3, 4, 8 ] {
+            let options = Options { mapped: true, jobs: NonZeroUsize::new( jobs ), ..strict.clone() };
+            let err = crate::run( Sum, source.clone(), &options ).await.unwrap_err();
+            assert_eq!( format!( "{err:#}" ), format!( "{streamed:#}" ), "{jobs} jobs" );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_overflow_matches_streaming() {
+        let path = TempPath::new( "mapped-overflow" );
+        // The first line, padded out to be a range of its own, and the
+        // rest each add up, but not together, so it is merging them that
+        // fails.
+        let mut text = format!( "{:0>400}\n", usize::MAX - 10 );
+        text += &"1\n".repeat( 100 );
+        fs::write( &path, &text ).unwrap();
+        let source = Arc::new( FileSource::new( path.to_path_buf() ) );
+
+        let options = Options { quiet: true, ..Options::default() };
+        let streamed = crate::run( Sum, source.clone(), &options ).await.unwrap_err();
+        assert!( format!( "{streamed:#}" ).starts_with( &format!( "workers: {}:12: ", path.display() ) ), "{streamed:#}" );
+        for jobs in [ 2, 3, 4, 8 ] {
+            let mapped = Options { mapped: true, jobs: NonZeroUsize::new( jobs ), ..options.clone() };
+            let err = crate::run( Sum, source.clone(), &mapped ).await.unwrap_err();
+            assert_eq!( format!( "{err:#}" ), format!( "{streamed:#}" ), "{jobs} jobs" );
+            assert_eq!( crate::Failure::of( &err ), crate::Failure::Parse );
+        }
+    }
+}
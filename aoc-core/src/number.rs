@@ -0,0 +1,159 @@
+use anyhow::{ Result };
+
+use num_bigint::BigUint;
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::registry::Params;
+
+/// How a solver adds up its answer: in a `usize`, failing rather than
+/// wrapping on overflow, or without bound for stress inputs.
+#[derive( Debug, Clone, Copy, Default, PartialEq, Eq )]
+pub enum Arithmetic {
+    #[default]
+    Checked,
+    Big,
+}
+
+impl Arithmetic {
+    /// `Big` when the `big` parameter is true.
+    pub fn from_params( params: &Params ) -> Result<Arithmetic> {
+        Ok( if params.get_or( "big", false )? { Arithmetic::Big } else { Arithmetic::Checked } )
+    }
+
+    pub fn number( self, value: usize ) -> Number {
+        match self {
+            Arithmetic::Checked => Number::Small( value ),
+            Arithmetic::Big => Number::Big( BigUint::from( value ) ),
+        }
+    }
+}
+
+/// An answer, or a line's part in one, as `Arithmetic` counts it.
+#[derive( Debug, Clone )]
+pub enum Number {
+    Small( usize ),
+    Big( BigUint ),
+}
+
+/// `a + b`, or an `Overflow` if it does not fit.
+pub fn add( a: usize, b: usize ) -> Result<usize, Overflow> {
+    a.checked_add( b ).ok_or_else( || Overflow::new( a, "+", b ) )
+}
+
+/// `a * b`, or an `Overflow` if it does not fit.
+pub fn mul( a: usize, b: usize ) -> Result<usize, Overflow> {
+    a.checked_mul( b ).ok_or_else( || Overflow::new( a, "*", b ) )
+}
+
+impl Number {
+    pub fn checked_add( self, other: Number ) -> Result<Number, Overflow> {
+        match ( self, other ) {
+            ( Number::Small( a ), Number::Small( b ) ) => add( a, b ).map( Number::Small ),
+            ( a, b ) => Ok( Number::Big( a.into_big() + b.into_big() ) ),
+        }
+    }
+
+    pub fn checked_mul( self, other: Number ) -> Result<Number, Overflow> {
+        match ( self, other ) {
+            ( Number::Small( a ), Number::Small( b ) ) => mul( a, b ).map( Number::Small ),
+            ( a, b ) => Ok( Number::Big( a.into_big() * b.into_big() ) ),
+        }
+    }
+
+    fn into_big( self ) -> BigUint {
+        match self {
+            Number::Small( value ) => BigUint::from( value ),
+            Number::Big( value ) => value,
+        }
+    }
+}
+
+/// Numbers compare by value, whichever way they are held.
+impl Ord for Number {
+    fn cmp( &self, other: &Number ) -> Ordering {
+        match ( self, other ) {
+            ( Number::Small( a ), Number::Small( b ) ) => a.cmp( b ),
+            ( Number::Big( a ), Number::Big( b ) ) => a.cmp( b ),
+            ( Number::Small( a ), Number::Big( b ) ) => BigUint::from( *a ).cmp( b ),
+            ( Number::Big( a ), Number::Small( b ) ) => a.cmp( &BigUint::from( *b ) ),
+        }
+    }
+}
+
+impl PartialEq for Number {
+    fn eq( &self, other: &Number ) -> bool {
+        self.cmp( other ) == Ordering::Equal
+    }
+}
+
+impl Eq for Number {}
+
+impl PartialOrd for Number {
+    fn partial_cmp( &self, other: &Number ) -> Option<Ordering> {
+        Some( self.cmp( other ) )
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        match self {
+            Number::Small( value ) => write!( f, "{value}" ),
+            Number::Big( value ) => write!( f, "{value}" ),
+        }
+    }
+}
+
+/// Checked arithmetic that went past `usize::MAX`.
+#[derive( Debug, Clone, PartialEq, Eq )]
+pub struct Overflow {
+    expression: String,
+}
+
+impl Overflow {
+    fn new( a: usize, operator: &str, b: usize ) -> Overflow {
+        Overflow { expression: format!( "{a} {operator} {b}" ) }
+    }
+}
+
+impl fmt::Display for Overflow {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        write!( f, "{} is too large for a usize", self.expression )
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+#[cfg(test)]
+mod number_tests {
+    use super::*;
+    use crate::Failure;
+
+    #[test]
+    fn test_checked() {
+        let checked = Arithmetic::Checked;
+        assert_eq!( checked.number( 2 ).checked_add( checked.number( 3 ) ).unwrap(), Number::Small( 5 ) );
+        assert_eq!( checked.number( 2 ).checked_mul( checked.number( 3 ) ).unwrap(), Number::Small( 6 ) );
+
+        let err = anyhow::Error::from( checked.number( usize::MAX ).checked_add( checked.number( 1 ) ).unwrap_err() );
+        assert_eq!( err.to_string(), format!( "{} + 1 is too large for a usize", usize::MAX ) );
+        assert_eq!( Failure::of( &err ), Failure::Parse );
+        assert!( checked.number( usize::MAX ).checked_mul( checked.number( 2 ) ).is_err() );
+        assert!( checked.number( 3 ) < Arithmetic::Big.number( 4 ) );
+        assert_eq!( checked.number( 3 ), Arithmetic::Big.number( 3 ) );
+        assert!( Arithmetic::Big.number( usize::MAX ) > checked.number( 2 ) );
+    }
+
+    #[test]
+    fn test_big() {
+        let params = Params::parse( [ "big=true" ] ).unwrap();
+        let big = Arithmetic::from_params( &params ).unwrap();
+        assert_eq!( big, Arithmetic::Big );
+
+        let product = big.number( usize::MAX ).checked_mul( big.number( usize::MAX ) ).unwrap();
+        let sum = product.checked_add( big.number( 1 ) ).unwrap();
+        assert_eq!( sum.to_string(), "340282366920938463426481119284349108226" );
+        assert_eq!( Arithmetic::from_params( &Params::new() ).unwrap(), Arithmetic::Checked );
+    }
+}
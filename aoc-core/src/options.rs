@@ -0,0 +1,79 @@
+use crate::frame::Framing;
+use crate::interrupt::Interrupt;
+use crate::limits::Limits;
+use crate::outcome::Outcome;
+use crate::reject::Rejects;
+use crate::timings::Timings;
+
+use tokio::sync::mpsc::Sender;
+
+use std::num::NonZeroUsize;
+
+/// How a run reads its input.
+#[derive( Debug, Clone, Default )]
+pub struct Options {
+    /// Replace invalid UTF-8 with U+FFFD instead of failing the run.
+    pub lossy: bool,
+    /// How to cut the input into records, instead of the solver's own
+    /// choice.
+    pub framing: Option<Framing>,
+    /// Bounds the input must stay within.
+    pub limits: Limits,
+    /// Lines processed at once, one per CPU when not set. A single job
+    /// processes them in input order.
+    pub jobs: Option<NonZeroUsize>,
+    /// How many chunks or lines each stage may queue up for the next
+    /// before it has to wait, `CAPACITY` when not set.
+    pub capacity: Option<NonZeroUsize>,
+    /// The most bytes the reader asks for at once, `BUFFER_SIZE` when not
+    /// set.
+    pub buffer_size: Option<NonZeroUsize>,
+    /// Map files into memory and split them into a range of lines per job,
+    /// each framed and processed on a thread of its own, instead of
+    /// streaming them. See `mapped::can_split` for what is streamed all the
+    /// same.
+    pub mapped: bool,
+    /// Fail on the first line the solver cannot parse, instead of skipping
+    /// it.
+    pub strict: bool,
+    /// Where to write every line the solver cannot parse.
+    pub rejects: Option<Rejects>,
+    /// Where to hand the outcome of every line, for per-line output.
+    pub lines: Option<Sender<Outcome>>,
+    /// Hand outcomes on in input order rather than as they are ready.
+    pub ordered: bool,
+    /// Leave out banners, the summary of skipped lines and the line a
+    /// strict run failed on, which otherwise go to stderr.
+    pub quiet: bool,
+    /// Show how far the run has got on stderr, as long as stderr is a
+    /// terminal.
+    pub progress: bool,
+    /// Where to add up the time each stage spends busy, and the slowest
+    /// lines.
+    pub timings: Option<Timings>,
+    /// Stops the run early, ending it with what was folded by then.
+    pub interrupt: Interrupt,
+}
+
+/// Queue length between stages when `Options::capacity` is not set.
+pub const CAPACITY:NonZeroUsize = NonZeroUsize::new( 1024 ).unwrap();
+
+/// Bytes per read when `Options::buffer_size` is not set.
+pub const BUFFER_SIZE:NonZeroUsize = NonZeroUsize::new( 64 * 1024 ).unwrap();
+
+impl Options {
+    /// The number of jobs to run with.
+    pub fn jobs( &self ) -> NonZeroUsize {
+        self.jobs.or_else( || std::thread::available_parallelism().ok() ).unwrap_or( NonZeroUsize::MIN )
+    }
+
+    /// The queue length between stages.
+    pub fn capacity( &self ) -> NonZeroUsize {
+        self.capacity.unwrap_or( CAPACITY )
+    }
+
+    /// The most bytes to read at once.
+    pub fn buffer_size( &self ) -> NonZeroUsize {
+        self.buffer_size.unwrap_or( BUFFER_SIZE )
+    }
+}
@@ -0,0 +1,14 @@
+use crate::line::Line;
+
+/// What came of one line, for per-line output.
+#[derive( Debug, Clone, PartialEq )]
+pub struct Outcome {
+    pub line: Line,
+    /// How the solver describes the record, such as a game's id and
+    /// maxima; empty if the line did not parse.
+    pub fields: Vec<( &'static str, String )>,
+    /// What the line added to the answer, `None` if it did not count.
+    pub value: Option<String>,
+    /// Why the line was skipped, if it did not parse.
+    pub skipped: Option<String>,
+}
@@ -0,0 +1,686 @@
+use anyhow::{ Context, Result };
+use bytes::Bytes;
+use tokio::sync::mpsc::{ Receiver, Sender };
+
+use tokio::task::JoinSet;
+
+use std::collections::{ BTreeMap, HashMap };
+use std::sync::Arc;
+use std::time::{ Duration, Instant };
+
+use tracing::{ Instrument, debug, debug_span, info, trace, warn };
+
+use crate::diagnostic;
+use crate::failure::InternalError;
+use crate::frame::Framer;
+use crate::interrupt::Interrupted;
+use crate::line::Line;
+use crate::number::Overflow;
+use crate::options::Options;
+use crate::outcome::Outcome;
+use crate::progress::{ Progress, Stage };
+use crate::reject::Rejected;
+use crate::solver::Solver;
+use crate::timings::{ Slowest, Timings };
+
+pub async fn chunker(
+    mut rx: Receiver<Bytes>,
+    tx: Sender<Line>,
+    mut framer: Framer,
+    progress: Arc<Progress>,
+) -> Result<()> {
+
+    while let Some( chunk ) = rx.recv().await {
+        let mut started = Instant::now();
+        framer.push( chunk )?;
+        let mut framed = 0;
+        while let Some( record ) = framer.next_record()? {
+            progress.spent( Stage::Framer, started.elapsed() );
+            if tx.send( record ).await.is_err() {
+                // The workers have stopped, and report why themselves.
+                return Ok( () );
+            }
+            started = Instant::now();
+            framed += 1;
+        }
+        progress.spent( Stage::Framer, started.elapsed() );
+        progress.framed( framed );
+    }
+
+    if let Some( record ) = framer.finish()? {
+        progress.framed( 1 );
+        // As above, a closed channel is reported by the workers.
+        let _ = tx.send( record ).await;
+    }
+    debug!( lines = progress.items( Stage::Framer ), "framed every record" );
+    Ok( () )
+}
+
+#[cfg(test)]
+mod chunker_tests {
+    use super::*;
+    use bytestring::ByteString;
+    use crate::frame::Framing;
+    use crate::limits::Limits;
+    use tokio::sync::mpsc::{ channel };
+
+    #[tokio::test]
+    async fn test_long_line() {
+        let input_output:Vec<(&str, Vec<&str>)> = vec![
+            ("abc\n123\ncome-with-me\n", [ "abc", "123", "come-with-me"].to_vec() ),
+            ("neah\nnee\nwee\n", [ "neah", "nee", "wee"].to_vec() ),
+            ("neah\nnee\nwee\nfeh\nleh\njeh\ntee\n", [ "neah", "nee", "wee", "feh", "leh", "jeh", "tee"].to_vec() ),
+            ("neah\nnee\nwee\nfeh\nleh\njeh\ntee", [ "neah", "nee", "wee", "feh", "leh", "jeh", "tee"].to_vec() ),
+            ("neah\r\nnee\r\nwee", [ "neah", "nee", "wee"].to_vec() ),
+        ];
+
+
+        for ( input, output) in input_output {
+            let (tx,rx) = channel::<Bytes>( 64 );
+            let (tx_2,mut rx_2) = channel::<Line>( 64 );
+            let framer = Framer::new( Arc::from( "input" ), Framing::Lines, false, Limits::default() );
+            let chunker_future = tokio::spawn(chunker(rx, tx_2, framer, Arc::default()));
+
+            let send = tx.send( Bytes::from( input ) ).await;
+            assert!( send.is_ok() );
+            drop( tx );
+
+            for out in output {
+                assert_eq!( rx_2.recv().await.map( | line | line.text ), Some( ByteString::from( out ) ) );
+            }
+
+            assert_eq!( rx_2.recv().await, None );
+            let chunker_result = chunker_future.await;
+            assert!( chunker_result.is_ok() );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_paragraphs() {
+        let (tx,rx) = channel::<Bytes>( 64 );
+        let (tx_2,mut rx_2) = channel::<Line>( 64 );
+        let framer = Framer::new( Arc::from( "input" ), Framing::Paragraphs, false, Limits::default() );
+        let chunker_future = tokio::spawn(chunker(rx, tx_2, framer, Arc::default()));
+
+        for piece in [ "ab\ncd", "\n\nef\n", "\ngh" ] {
+            assert!( tx.send( Bytes::from( piece ) ).await.is_ok() );
+        }
+        drop( tx );
+
+        for out in [ "ab\ncd", "ef", "gh" ] {
+            assert_eq!( rx_2.recv().await.map( | line | line.text ), Some( ByteString::from( out ) ) );
+        }
+        assert_eq!( rx_2.recv().await, None );
+        assert!( chunker_future.await.is_ok() );
+    }
+}
+
+/// Lines handed to a worker at a time, so the cost of a task is shared
+/// by many lines.
+pub(crate) const BATCH_SIZE:usize = 256;
+
+/// A line and what came of processing it.
+struct Processed<S: Solver> {
+    line: Line,
+    contribution: Result<Option<S::Contribution>>,
+    /// The solver's description of the record, only asked for when
+    /// outcomes are being handed on.
+    fields: Vec<( &'static str, String )>,
+    /// How long the line took, only measured for `options.timings`.
+    elapsed: Option<Duration>,
+}
+
+/// Hands the outcome of every line on to `Options::lines`, batch by batch
+/// as they finish, or re-sequenced into input order under
+/// `Options::ordered`.
+struct Emitter {
+    tx: Option<Sender<Outcome>>,
+    ordered: bool,
+    /// The batch due next, in order.
+    next: usize,
+    /// Batches that finished ahead of one before them.
+    pending: BTreeMap<usize, Vec<Outcome>>,
+}
+
+impl Emitter {
+    async fn emit( &mut self, batch: usize, outcomes: Vec<Outcome> ) {
+        let Some( tx ) = &self.tx else {
+            return;
+        };
+        if !self.ordered {
+            for outcome in outcomes {
+                // Nobody listening any more is not the run's problem.
+                let _ = tx.send( outcome ).await;
+            }
+            return;
+        }
+
+        self.pending.insert( batch, outcomes );
+        while let Some( outcomes ) = self.pending.remove( &self.next ) {
+            self.next += 1;
+            for outcome in outcomes {
+                let _ = tx.send( outcome ).await;
+            }
+        }
+    }
+}
+
+/// How many skipped lines the summary on stderr quotes.
+const SKIPPED_EXAMPLES:usize = 3;
+
+/// Lines the solver could not parse, skipped rather than failing the run.
+#[derive( Debug, Default )]
+pub(crate) struct Skipped {
+    count: usize,
+    examples: Vec<String>,
+}
+
+impl Skipped {
+    fn note( &mut self, line: &Line, reason: &anyhow::Error ) {
+        self.count += 1;
+        if self.examples.len() < SKIPPED_EXAMPLES {
+            self.examples.push( diagnostic::render( line, reason ) );
+        }
+    }
+
+    /// Add the lines skipped later in the input.
+    pub(crate) fn merge( &mut self, other: Skipped ) {
+        self.count += other.count;
+        let room = SKIPPED_EXAMPLES - self.examples.len();
+        self.examples.extend( other.examples.into_iter().take( room ) );
+    }
+
+    fn report( &self, lines: usize ) {
+        if self.count == 0 {
+            return;
+        }
+        eprintln!( "Skipped {} of {lines} lines that did not parse:", self.count );
+        for example in &self.examples {
+            eprintln!( "{example}" );
+        }
+        if self.count > self.examples.len() {
+            eprintln!( "..." );
+        }
+    }
+}
+
+/// Deal with a line the solver could not parse: write it to
+/// `options.rejects`, and fail with it under `options.strict` or else
+/// note it as skipped.
+pub(crate) fn skip( line: &Line, reason: &anyhow::Error, options: &Options, skipped: &mut Skipped ) -> Result<()> {
+    if let Some( rejects ) = &options.rejects {
+        rejects.write( line, reason )?;
+    }
+    if options.strict {
+        return Err( Rejected::new( line, reason ).into() );
+    }
+    trace!( location = %line.location(), reason = %format!( "{reason:#}" ), "skipped line" );
+    skipped.note( line, reason );
+    Ok( () )
+}
+
+/// Show the line a strict run failed on, with where it went wrong pointed
+/// out, unless `options.quiet`. Only the line reported is shown, however
+/// many failed while it was found.
+pub(crate) fn show_rejected( err: &anyhow::Error, options: &Options ) {
+    if options.quiet {
+        return;
+    }
+    if let Some( rejected ) = err.chain().find_map( | cause | cause.downcast_ref::<Rejected>() ) {
+        eprintln!( "{}", rejected.rendered() );
+    }
+}
+
+/// Close a run over `lines` lines that was not interrupted: the banner and
+/// the summary of skipped lines, unless `options.quiet`, and the rejects.
+pub(crate) fn summarise( options: &Options, skipped: &Skipped, lines: usize ) -> Result<()> {
+    report( options, skipped, lines );
+    if let Some( rejects ) = &options.rejects {
+        rejects.flush()?;
+    }
+    Ok( () )
+}
+
+/// The banner and the summary of skipped lines, unless `options.quiet`.
+fn report( options: &Options, skipped: &Skipped, lines: usize ) {
+    if !options.quiet {
+        eprintln!( "That's all she wrote..." );
+        skipped.report( lines );
+    }
+}
+
+/// The answer the workers folded, along with the lines they skipped, to
+/// be summarised only once the stages in front of them have ended cleanly
+/// too.
+#[derive( Debug )]
+pub struct Folded<A> {
+    pub answer: A,
+    lines: usize,
+    skipped: Skipped,
+}
+
+impl<A> Folded<A> {
+    /// The banner and the summary of skipped lines, unless
+    /// `options.quiet`, handing back the answer.
+    pub fn summarise( self, options: &Options ) -> A {
+        report( options, &self.skipped, self.lines );
+        self.answer
+    }
+}
+
+/// Process lines on at most `options.jobs()` workers, one batch of lines
+/// each, and fold their contributions into the answer. While every worker
+/// is busy no more lines are taken from `rx`, holding back the stages in
+/// front.
+///
+/// With one job the batches are folded strictly in input order; with more
+/// they are folded as they finish, and only the outcomes handed on to
+/// `options.lines` are put back in order, under `options.ordered`. Blank
+/// lines are passed over; lines that do not parse are counted and
+/// summarised on stderr, written to `options.rejects`, and fail the run
+/// under `options.strict`. Once `options.interrupt` is triggered, or a line
+/// has failed the run, no more lines are taken; the batches in flight are
+/// folded, and the run ends with `Interrupted`, or with the failure of the
+/// earliest batch, as the one a single job would have reported.
+///
+/// The summary is left to the caller, through `Folded::summarise`, as
+/// lines running out does not say whether the input was read to its end.
+pub async fn distribute_work<S: Solver>(
+    solver: Arc<S>,
+    mut rx: Receiver<Line>,
+    options: Options,
+    progress: Arc<Progress>,
+) -> Result<Folded<S::Answer>> {
+
+    let jobs = options.jobs();
+    let describe = options.lines.is_some();
+    let mut emitter = Emitter { tx: options.lines.clone(), ordered: options.ordered, next: 0, pending: BTreeMap::new() };
+
+    let mut join_set: JoinSet<Vec<Processed<S>>> = JoinSet::new();
+    // Which batch each running task has and where it starts, to say where
+    // a failed one was.
+    let mut starts = HashMap::new();
+    let mut running_total = solver.initial();
+
+    let mut batch = Vec::with_capacity( BATCH_SIZE );
+    let mut more = true;
+    let mut batches = 0;
+    // Every worker is joined, even after one has failed, so that all of
+    // their failures are reported.
+    let mut failures = Vec::new();
+    let mut lines = 0;
+    let mut skipped = Skipped::default();
+    let mut interrupted = false;
+    // The earliest batch to fail, and why.
+    let mut failed: Option<( usize, anyhow::Error )> = None;
+    let timed = options.timings.is_some();
+    let mut slowest = Slowest::new( options.timings.as_ref().map_or( 0, Timings::limit ) );
+
+    while more || !join_set.is_empty() {
+        if more && join_set.len() < jobs.get() {
+            let received = tokio::select! {
+                biased;
+                _ = options.interrupt.triggered() => {
+                    // What is in flight is folded; the rest is dropped.
+                    batch.clear();
+                    interrupted = true;
+                    None
+                },
+                line = rx.recv() => line,
+            };
+            match received {
+                // A blank line holds no record, so it is not worth a reject.
+                Some( line ) if line.text.trim().is_empty() => {
+                    progress.processed( 1 );
+                    continue;
+                },
+                Some( line ) => batch.push( line ),
+                None => more = false,
+            }
+            if batch.len() == BATCH_SIZE || ( !more && !batch.is_empty() ) {
+                let lines = std::mem::replace( &mut batch, Vec::with_capacity( BATCH_SIZE ) );
+                let solver = solver.clone();
+                let progress = progress.clone();
+                let start = lines[0].location();
+                let span = debug_span!( "batch", index = batches, start = %start );
+                let task = join_set.spawn( async move {
+                    let started = Instant::now();
+                    let processed:Vec<Processed<S>> = lines.into_iter().map( | line | {
+                        let line_started = timed.then( Instant::now );
+                        let mut fields = Vec::new();
+                        let contribution = solver.parse( &line.text ).and_then( | record | {
+                            if describe {
+                                fields = solver.describe( &record );
+                            }
+                            solver.contribution( record )
+                        } );
+                        let elapsed = line_started.map( | started | started.elapsed() );
+                        Processed { line, contribution, fields, elapsed }
+                    } ).collect();
+                    progress.spent( Stage::Workers, started.elapsed() );
+                    trace!( lines = processed.len(), elapsed = ?started.elapsed(), "processed batch" );
+                    processed
+                }.instrument( span ) );
+                starts.insert( task.id(), ( batches, start ) );
+                batches += 1;
+            }
+            continue;
+        }
+
+        match join_set.join_next_with_id().await {
+            Some( Ok( ( id, results ) ) ) => {
+                let ( batch, _ ) = starts.remove( &id ).unwrap_or_default();
+                lines += results.len();
+                progress.processed( results.len() );
+                let started = Instant::now();
+                let mut outcomes = Vec::new();
+                let mut failure = None;
+                for Processed { line, contribution, fields, elapsed } in results {
+                    if let Some( elapsed ) = elapsed {
+                        slowest.note( &line, elapsed );
+                    }
+                    let mut outcome = describe.then( || Outcome { line: line.clone(), fields, value: None, skipped: None } );
+                    match contribution {
+                        Ok( Some( contribution ) ) => {
+                            if let Some( outcome ) = &mut outcome {
+                                outcome.value = Some( contribution.to_string() );
+                            }
+                            running_total = solver.fold( running_total, contribution ).context( line.location() )?;
+                        },
+                        Ok( None ) => {},
+                        Err( reason ) if reason.chain().any( | cause | cause.is::<Overflow>() ) => {
+                            failure = Some( reason.context( line.location() ) );
+                        },
+                        Err( reason ) => match skip( &line, &reason, &options, &mut skipped ) {
+                            Ok( () ) => {
+                                if let Some( outcome ) = &mut outcome {
+                                    outcome.skipped = Some( format!( "{reason:#}" ) );
+                                }
+                            },
+                            Err( err ) => failure = Some( err ),
+                        },
+                    }
+                    if failure.is_some() {
+                        break;
+                    }
+                    outcomes.extend( outcome );
+                }
+                progress.spent( Stage::Reducer, started.elapsed() );
+                emitter.emit( batch, outcomes ).await;
+                if let Some( failure ) = failure {
+                    // Batches before this one may yet fail on an earlier
+                    // line, so they are still folded; those after it are
+                    // not started.
+                    more = false;
+                    if failed.as_ref().is_none_or( | ( earliest, _ ) | batch < *earliest ) {
+                        failed = Some( ( batch, failure ) );
+                    }
+                }
+            },
+            Some( Err( err ) ) => {
+                let ( batch, start ) = starts.remove( &err.id() ).unwrap_or_default();
+                warn!( %start, "batch failed" );
+                failures.push( format!( "batch from {start} {}", InternalError::from( err ) ) );
+                // Later batches are not held back waiting for this one.
+                emitter.emit( batch, Vec::new() ).await;
+            },
+            None => {},
+        }
+    }
+
+    debug!( lines, skipped = skipped.count, batches, "folded every batch" );
+    if let Some( timings ) = &options.timings {
+        timings.record_lines( slowest );
+    }
+
+    if let Some( rejects ) = &options.rejects {
+        rejects.flush()?;
+    }
+
+    if let Some( ( _, err ) ) = failed {
+        show_rejected( &err, &options );
+        return Err( err );
+    }
+
+    if interrupted {
+        info!( lines, "interrupted" );
+        return Err( Interrupted::new( running_total, lines ).into() );
+    }
+
+    if !failures.is_empty() {
+        let failed = failures.len();
+        failures.sort();
+        failures.dedup();
+        let message = format!( "{failed} of {batches} batches failed: {}", failures.join( "; " ) );
+        return Err( InternalError::new( message ).into() );
+    }
+
+    Ok( Folded { answer: running_total, lines, skipped } )
+}
+
+#[cfg(test)]
+mod distribute_tests {
+    use super::*;
+    use std::num::NonZeroUsize;
+    use crate::reject::Rejects;
+    use crate::test_support::{ Sum, TempPath };
+    use bytestring::ByteString;
+    use tokio::sync::mpsc::{ channel };
+
+    /// The `index`th line of a test input.
+    fn line_at( index: usize, text: &str ) -> Line {
+        Line { source: Arc::from( "input" ), number: index + 1, byte_offset: 0, text: ByteString::from( text ) }
+    }
+
+    #[tokio::test]
+    async fn test_sum_skips_unparseable() {
+        let (tx,rx) = channel::<Line>( 64 );
+        for ( number, line ) in [ "1", "2", "three", "", "4" ].into_iter().enumerate() {
+            assert!( tx.send( line_at( number, line ) ).await.is_ok() );
+        }
+        drop( tx );
+
+        let total = distribute_work( Arc::new( Sum ), rx, Options::default(), Arc::default() ).await;
+        assert_eq!( total.ok().map( | folded | folded.answer ), Some( 7 ) );
+    }
+
+    #[tokio::test]
+    async fn test_overflow_names_the_line() {
+        let (tx,rx) = channel::<Line>( 64 );
+        for ( number, line ) in [ "1", &usize::MAX.to_string() ].into_iter().enumerate() {
+            assert!( tx.send( line_at( number, line ) ).await.is_ok() );
+        }
+        drop( tx );
+
+        let err = distribute_work( Arc::new( Sum ), rx, Options::default(), Arc::default() ).await.unwrap_err();
+        assert_eq!( format!( "{err:#}" ), format!( "input:2: 1 + {} is too large for a usize", usize::MAX ) );
+    }
+
+    #[tokio::test]
+    async fn test_strict_and_rejects() {
+        let path = TempPath::new( "rejects" );
+        let options = Options { rejects: Some( Rejects::create( &path ).unwrap() ), ..Options::default() };
+
+        let send = | options: Options | async move {
+            let (tx,rx) = channel::<Line>( 64 );
+            for ( number, line ) in [ "1", "two", "3", "four" ].into_iter().enumerate() {
+                assert!( tx.send( line_at( number, line ) ).await.is_ok() );
+            }
+            drop( tx );
+            distribute_work( Arc::new( Sum ), rx, options, Arc::default() ).await
+        };
+
+        assert_eq!( send( options.clone() ).await.unwrap().answer, 4 );
+        let rejects = std::fs::read_to_string( &path ).unwrap();
+        assert_eq!( rejects, "input:2\tinvalid digit found in string\ttwo\ninput:4\tinvalid digit found in string\tfour\n" );
+
+        let err = send( Options { strict: true, ..Options::default() } ).await.unwrap_err();
+        assert!( err.is::<Rejected>() );
+        assert_eq!( err.to_string(), "input:2: invalid digit found in string" );
+    }
+
+    /// Fails on a line of its own only after a while, so that its batch
+    /// is folded after those that follow it.
+    struct Slow;
+
+    impl Solver for Slow {
+        type Record = usize;
+        type Contribution = usize;
+        type Answer = usize;
+
+        fn parse( &self, line: &str ) -> Result<usize> {
+            if line == "slow" {
+                std::thread::sleep( Duration::from_millis( 200 ) );
+            }
+            Ok( line.parse()? )
+        }
+        fn contribution( &self, record: usize ) -> Result<Option<usize>> {
+            Ok( Some( record ) )
+        }
+        fn initial( &self ) -> usize {
+            0
+        }
+        fn fold( &self, answer: usize, contribution: usize ) -> Result<usize> {
+            Ok( answer + contribution )
+        }
+        fn merge( &self, answer: usize, other: usize ) -> Result<usize> {
+            Ok( answer + other )
+        }
+    }
+
+    #[tokio::test( flavor = "multi_thread", worker_threads = 4 )]
+    async fn test_strict_reports_earliest_line() {
+        let (tx,rx) = channel::<Line>( 4 * BATCH_SIZE );
+        for number in 0..4 * BATCH_SIZE {
+            let text = match number {
+                0 => "slow",
+                number if number == BATCH_SIZE + 1 => "fast",
+                _ => "1",
+            };
+            assert!( tx.send( line_at( number, text ) ).await.is_ok() );
+        }
+        drop( tx );
+
+        let options = Options { jobs: NonZeroUsize::new( 4 ), strict: true, quiet: true, ..Options::default() };
+        let err = distribute_work( Arc::new( Slow ), rx, options, Arc::default() ).await.unwrap_err();
+        assert_eq!( err.to_string(), "input:1: invalid digit found in string" );
+    }
+
+    /// Panics on a line of its own.
+    struct Fragile;
+
+    impl Solver for Fragile {
+        type Record = usize;
+        type Contribution = usize;
+        type Answer = usize;
+
+        fn parse( &self, line: &str ) -> Result<usize> {
+            if line == "panic" {
+                panic!( "fragile" );
+            }
+            Ok( line.parse()? )
+        }
+        fn contribution( &self, record: usize ) -> Result<Option<usize>> {
+            Ok( Some( record ) )
+        }
+        fn initial( &self ) -> usize {
+            0
+        }
+        fn fold( &self, answer: usize, contribution: usize ) -> Result<usize> {
+            Ok( answer + contribution )
+        }
+        fn merge( &self, answer: usize, other: usize ) -> Result<usize> {
+            Ok( answer + other )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_worker_panic_fails() {
+        let (tx,rx) = channel::<Line>( 64 );
+        for ( number, line ) in [ "1", "panic", "2" ].into_iter().enumerate() {
+            assert!( tx.send( line_at( number, line ) ).await.is_ok() );
+        }
+        drop( tx );
+
+        let err = distribute_work( Arc::new( Fragile ), rx, Options::default(), Arc::default() ).await.unwrap_err();
+        assert!( err.is::<InternalError>() );
+        assert_eq!( err.to_string(), "1 of 1 batches failed: batch from input:1 panicked: fragile" );
+    }
+
+    #[tokio::test( flavor = "multi_thread", worker_threads = 4 )]
+    async fn test_ordered_outcomes() {
+        let (tx,rx) = channel::<Line>( 64 );
+        tokio::spawn( async move {
+            for number in 0..5_000 {
+                let text = if number % 7 == 3 { "x".to_string() } else { number.to_string() };
+                assert!( tx.send( line_at( number, &text ) ).await.is_ok() );
+            }
+        } );
+
+        let (tx_outcome,mut rx_outcome) = channel::<Outcome>( 64 );
+        let options = Options { jobs: NonZeroUsize::new( 8 ), lines: Some( tx_outcome ), ordered: true, ..Options::default() };
+        let total = tokio::spawn( distribute_work( Arc::new( Sum ), rx, options, Arc::default() ) );
+
+        let mut numbers = Vec::new();
+        while let Some( outcome ) = rx_outcome.recv().await {
+            let index = outcome.line.number - 1;
+            if index % 7 == 3 {
+                assert_eq!( outcome.skipped.as_deref(), Some( "invalid digit found in string" ) );
+            } else {
+                assert_eq!( outcome.value, Some( index.to_string() ) );
+            }
+            numbers.push( outcome.line.number );
+        }
+
+        assert_eq!( numbers, ( 1..=5_000 ).collect::<Vec<_>>() );
+        assert!( total.await.unwrap().is_ok() );
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_folds_in_flight() {
+        let (tx,rx) = channel::<Line>( 2_000 );
+        for number in 0..1_000 {
+            assert!( tx.send( line_at( number, "1" ) ).await.is_ok() );
+        }
+
+        let (tx_outcome,mut rx_outcome) = channel::<Outcome>( 64 );
+        let options = Options { lines: Some( tx_outcome ), ..Options::default() };
+        let interrupt = options.interrupt.clone();
+        let total = tokio::spawn( distribute_work( Arc::new( Sum ), rx, options, Arc::default() ) );
+
+        let mut outcomes = 0;
+        while rx_outcome.recv().await.is_some() {
+            outcomes += 1;
+            if outcomes == BATCH_SIZE {
+                interrupt.trigger();
+            }
+        }
+
+        // The input never ends, yet the run does, with every line it
+        // handed to a worker folded.
+        let err = total.await.unwrap().unwrap_err();
+        let interrupted = err.downcast_ref::<Interrupted>().unwrap();
+        assert_eq!( interrupted.lines(), outcomes );
+        assert_eq!( interrupted.answer(), outcomes.to_string() );
+        assert!( outcomes < 1_000 );
+        assert_eq!( outcomes % BATCH_SIZE, 0 );
+        drop( tx );
+    }
+
+    #[tokio::test]
+    async fn test_jobs_agree() {
+        let mut totals = Vec::new();
+        for jobs in [ 1, 2, 8 ] {
+            let (tx,rx) = channel::<Line>( 64 );
+            tokio::spawn( async move {
+                for number in 0..10_000 {
+                    assert!( tx.send( line_at( number, &number.to_string() ) ).await.is_ok() );
+                }
+            } );
+
+            let options = Options { jobs: NonZeroUsize::new( jobs ), ..Options::default() };
+            totals.push( distribute_work( Arc::new( Sum ), rx, options, Arc::default() ).await.unwrap().answer );
+        }
+        assert_eq!( totals, [ 49_995_000; 3 ] );
+    }
+}
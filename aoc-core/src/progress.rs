@@ -0,0 +1,190 @@
+use tokio::sync::Notify;
+use tokio::time::{ interval, MissedTickBehavior };
+
+use std::io::{ self, Write };
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicBool, AtomicU64, Ordering };
+use std::time::{ Duration, Instant };
+
+/// How often the progress line is redrawn.
+const TICK:Duration = Duration::from_millis( 250 );
+
+/// A stage of the pipeline, for the time it spends busy.
+#[derive( Debug, Clone, Copy, PartialEq, Eq )]
+pub enum Stage {
+    Reader,
+    Framer,
+    Workers,
+    Reducer,
+}
+
+impl Stage {
+    pub const ALL:[Stage; 4] = [ Stage::Reader, Stage::Framer, Stage::Workers, Stage::Reducer ];
+
+    pub fn name( self ) -> &'static str {
+        match self {
+            Stage::Reader => "reader",
+            Stage::Framer => "framer",
+            Stage::Workers => "workers",
+            Stage::Reducer => "reducer",
+        }
+    }
+}
+
+/// How far a run over one input has got, counted by each stage as it goes
+/// and read by `display`, along with the time each stage has been busy.
+#[derive( Debug )]
+pub struct Progress {
+    size: Option<u64>,
+    started: Instant,
+    bytes_read: AtomicU64,
+    lines_framed: AtomicU64,
+    lines_processed: AtomicU64,
+    // Nanoseconds, by `Stage`.
+    busy: [AtomicU64; 4],
+    done: AtomicBool,
+    finished: Notify,
+}
+
+impl Default for Progress {
+    fn default() -> Progress {
+        Progress::new( None )
+    }
+}
+
+impl Progress {
+    /// Progress through an input of `size` bytes, if that is known.
+    pub fn new( size: Option<u64> ) -> Progress {
+        Progress {
+            size,
+            started: Instant::now(),
+            bytes_read: AtomicU64::new( 0 ),
+            lines_framed: AtomicU64::new( 0 ),
+            lines_processed: AtomicU64::new( 0 ),
+            busy: Default::default(),
+            done: AtomicBool::new( false ),
+            finished: Notify::new(),
+        }
+    }
+
+    pub fn read( &self, bytes: usize ) {
+        self.bytes_read.fetch_add( bytes as u64, Ordering::Relaxed );
+    }
+
+    pub fn framed( &self, lines: usize ) {
+        self.lines_framed.fetch_add( lines as u64, Ordering::Relaxed );
+    }
+
+    pub fn processed( &self, lines: usize ) {
+        self.lines_processed.fetch_add( lines as u64, Ordering::Relaxed );
+    }
+
+    /// Count `elapsed` as time `stage` spent busy, rather than waiting on
+    /// the stages around it.
+    pub fn spent( &self, stage: Stage, elapsed: Duration ) {
+        self.busy[stage as usize].fetch_add( elapsed.as_nanos() as u64, Ordering::Relaxed );
+    }
+
+    pub fn busy( &self, stage: Stage ) -> Duration {
+        Duration::from_nanos( self.busy[stage as usize].load( Ordering::Relaxed ) )
+    }
+
+    /// How many items `stage` has handled: bytes for the reader, lines
+    /// for the others.
+    pub fn items( &self, stage: Stage ) -> u64 {
+        match stage {
+            Stage::Reader => self.bytes_read.load( Ordering::Relaxed ),
+            Stage::Framer => self.lines_framed.load( Ordering::Relaxed ),
+            Stage::Workers | Stage::Reducer => self.lines_processed.load( Ordering::Relaxed ),
+        }
+    }
+
+    /// Stop `display`, once every stage is done.
+    pub fn finish( &self ) {
+        self.done.store( true, Ordering::Relaxed );
+        self.finished.notify_one();
+    }
+
+    /// One line saying how far the run has got:
+    ///
+    /// ```text
+    /// 12.0 MiB of 48.0 MiB (25%), 1000000 lines framed, 990000 processed, 24.0 MiB/s, ETA 1.5s
+    /// ```
+    ///
+    /// Without a known size there is neither share nor ETA.
+    pub fn render( &self, elapsed: Duration ) -> String {
+        let read = self.bytes_read.load( Ordering::Relaxed );
+        let framed = self.lines_framed.load( Ordering::Relaxed );
+        let processed = self.lines_processed.load( Ordering::Relaxed );
+        let rate = read as f64 / elapsed.as_secs_f64().max( f64::EPSILON );
+
+        let mut line = match self.size {
+            Some( size ) => {
+                let share = ( read * 100 ).checked_div( size ).unwrap_or( 100 );
+                format!( "{} of {} ({share}%)", mebibytes( read ), mebibytes( size ) )
+            },
+            None => format!( "{} read", mebibytes( read ) ),
+        };
+        line += &format!( ", {framed} lines framed, {processed} processed, {}/s", mebibytes( rate as u64 ) );
+        if let Some( size ) = self.size.filter( | _ | rate > 0.0 ) {
+            let eta = Duration::from_secs_f64( size.saturating_sub( read ) as f64 / rate );
+            line += &format!( ", ETA {:.1?}", eta );
+        }
+        line
+    }
+}
+
+fn mebibytes( bytes: u64 ) -> String {
+    format!( "{:.1} MiB", bytes as f64 / ( 1024.0 * 1024.0 ) )
+}
+
+/// Redraw `progress` on one line of stderr until it is finished, then
+/// clear it away again.
+pub async fn display( progress: Arc<Progress> ) {
+    let mut ticks = interval( TICK );
+    ticks.set_missed_tick_behavior( MissedTickBehavior::Skip );
+
+    while !progress.done.load( Ordering::Relaxed ) {
+        tokio::select! {
+            _ = ticks.tick() => {
+                let line = progress.render( progress.started.elapsed() );
+                let _ = write!( io::stderr(), "\r\x1b[K{line}" );
+            },
+            _ = progress.finished.notified() => {},
+        }
+    }
+    let _ = write!( io::stderr(), "\r\x1b[K" );
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let progress = Progress::new( Some( 48 * 1024 * 1024 ) );
+        progress.read( 12 * 1024 * 1024 );
+        progress.framed( 1000 );
+        progress.framed( 500 );
+        progress.processed( 1024 );
+        assert_eq!(
+            progress.render( Duration::from_secs( 2 ) ),
+            "12.0 MiB of 48.0 MiB (25%), 1500 lines framed, 1024 processed, 6.0 MiB/s, ETA 6.0s",
+        );
+
+        let progress = Progress::default();
+        progress.read( 1024 * 1024 );
+        assert_eq!(
+            progress.render( Duration::from_secs( 1 ) ),
+            "1.0 MiB read, 0 lines framed, 0 processed, 1.0 MiB/s",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_display_finishes() {
+        let progress = Arc::new( Progress::default() );
+        let display = tokio::spawn( display( progress.clone() ) );
+        progress.finish();
+        tokio::time::timeout( Duration::from_secs( 5 ), display ).await.unwrap().unwrap();
+    }
+}
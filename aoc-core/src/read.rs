@@ -0,0 +1,251 @@
+use anyhow::{ Context, Result };
+
+use std::io::{ ErrorKind, Read };
+use std::num::NonZeroUsize;
+
+use bytes::{ Bytes, BytesMut };
+
+use tokio::sync::mpsc::{ Sender };
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use tracing::debug;
+
+use crate::decompress::decompress;
+use crate::progress::{ Progress, Stage };
+use crate::source::InputSource;
+
+/// Read `source` to its end as `read_stream` does, decompressing it on the
+/// way if it is compressed.
+pub async fn read_input( source: Arc<dyn InputSource>, tx: Sender<Bytes>, progress: Arc<Progress>, buffer_size: NonZeroUsize ) -> Result<()> {
+
+    let name = source.name();
+
+    // Opening blocks too, until a writer comes along for a FIFO or a
+    // client connects to a socket.
+    let ( reader, compression ) = tokio::task::spawn_blocking( {
+        let name = name.clone();
+        move || {
+            let reader = source.open()?;
+            decompress( reader, &name ).context( format!( "Unable to read {name}" ) )
+        }
+    } ).await??;
+
+    let context = match compression {
+        Some( compression ) => format!( "Unable to read {name} as {}", compression.name() ),
+        None => format!( "Unable to read {name}" ),
+    };
+    read_stream( reader, tx, progress, buffer_size ).await.context( context )
+}
+
+/// Read `reader` to its end, sending the raw bytes on in chunks of at
+/// most `buffer_size` and counting them in `progress`.
+///
+/// Each read runs on the blocking pool, so a slow disk or a quiet pipe
+/// holds up no runtime thread, and dropping the future stops the reading
+/// once the read under way returns.
+///
+/// Only a zero-length read ends the input: pipes, FIFOs and sockets hand
+/// back short reads long before they are done.
+pub async fn read_stream<R: Read + Send + 'static>( mut reader: R, tx: Sender<Bytes>, progress: Arc<Progress>, buffer_size: NonZeroUsize ) -> Result<()> {
+
+    // Chunks are split off this buffer rather than copied out of it, and
+    // its allocation is reused once the framer is done with them.
+    let mut buffer = BytesMut::new();
+
+    loop {
+        buffer.resize( buffer_size.get(), 0 );
+
+        let result;
+        ( reader, buffer, result ) = tokio::task::spawn_blocking( {
+            let progress = progress.clone();
+            move || {
+                let started = Instant::now();
+                let result = reader.read( &mut buffer );
+                progress.spent( Stage::Reader, started.elapsed() );
+                ( reader, buffer, result )
+            }
+        } ).await?;
+
+        let read = match result {
+            Ok( 0 ) => break,
+            Ok( read ) => read,
+            Err( err ) if err.kind() == ErrorKind::Interrupted => continue,
+            Err( err ) => return Err( err.into() ),
+        };
+
+        buffer.truncate( read );
+        progress.read( read );
+        if tx.send( buffer.split().freeze() ).await.is_err() {
+            // The framer has stopped, and reports why itself.
+            break;
+        }
+    }
+
+    debug!( bytes = progress.items( Stage::Reader ), "read to the end" );
+
+    Ok( () )
+}
+
+#[cfg(test)]
+mod file_tests {
+    use super::*;
+    use crate::source::{ FileSource, Memory };
+    use tokio::sync::mpsc::{ channel };
+
+    fn size( bytes: usize ) -> NonZeroUsize {
+        NonZeroUsize::new( bytes ).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_read_file() {
+        let output:Vec<String> = vec![
+            "abc\n".to_string(),
+            "def\n".to_string(),
+            "ghi\n".to_string(),
+            "jkl\n".to_string(),
+            "mno\n".to_string(),
+            "pqr\n".to_string(),
+            "stu\n".to_string(),
+            "vwx\n".to_string(),
+            "yz\n".to_string(),
+            "\n".to_string(),
+        ];
+
+        let output_stringified = output.iter().fold( "".to_string(), | a, b| format!( "{}{}", a, b) );
+
+        for buf_size in [ 1, 3, 16, output_stringified.len(), 64 * 1024 ] {
+            let (tx,mut rx) = channel::<Bytes>( 64 );
+
+            let source = Arc::new( Memory::new( "input", &output_stringified ) );
+            let read_result = read_input( source, tx, Arc::default(), size( buf_size ) ).await;
+
+            let mut current_index = 0;
+
+            while let Some( read_line ) = rx.recv().await {
+                let expected_result = if current_index + buf_size > output_stringified.len() {
+                    &output_stringified[current_index..]
+                } else {
+                    &output_stringified[current_index..(current_index+buf_size)]
+                };
+
+                assert_eq!( read_line, expected_result.as_bytes(), "buffer size {buf_size}" );
+
+                current_index += buf_size;
+            }
+
+            assert!( current_index >= output_stringified.len(), "buffer size {buf_size}" );
+            assert!( read_result.is_ok() );
+        }
+
+    }
+
+    #[tokio::test]
+    async fn test_read_typographic_file() {
+        const TEXT:&str = "“one” — 2 ‘three’\nfour½ 🎄 five\n«six» seven…\n";
+
+        let (tx,mut rx) = channel::<Bytes>( 64 );
+
+        let source = Arc::new( Memory::new( "typographic", TEXT ) );
+        let read_result = read_input( source, tx, Arc::default(), size( 16 ) ).await;
+        assert!( read_result.is_ok() );
+
+        let mut contents = Vec::new();
+        while let Some( chunk ) = rx.recv().await {
+            contents.extend_from_slice( &chunk );
+        }
+
+        assert_eq!( contents, TEXT.as_bytes() );
+    }
+
+    /// Hands out its input a few bytes at a time, interrupted every other
+    /// call, then fails if `fail_at_end` is set.
+    struct Trickle {
+        input: Vec<u8>,
+        position: usize,
+        calls: usize,
+        fail_at_end: bool,
+    }
+
+    impl Read for Trickle {
+        fn read( &mut self, buf: &mut [u8] ) -> std::io::Result<usize> {
+            self.calls += 1;
+            if self.calls.is_multiple_of( 2 ) {
+                return Err( ErrorKind::Interrupted.into() );
+            }
+            if self.position == self.input.len() && self.fail_at_end {
+                return Err( std::io::Error::other( "connection reset" ) );
+            }
+            let read = ( self.input.len() - self.position ).min( buf.len() ).min( 3 );
+            buf[..read].copy_from_slice( &self.input[self.position..self.position + read] );
+            self.position += read;
+            Ok( read )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_short_reads() {
+        let input = "abc\ndef\nghi\njkl\nmno\npqr\n";
+        let reader = Trickle { input: input.as_bytes().to_vec(), position: 0, calls: 0, fail_at_end: false };
+
+        let (tx,mut rx) = channel::<Bytes>( 64 );
+        let read_result = read_stream( reader, tx, Arc::default(), size( 16 ) ).await;
+        assert!( read_result.is_ok() );
+
+        let mut contents = Vec::new();
+        while let Some( chunk ) = rx.recv().await {
+            contents.extend_from_slice( &chunk );
+        }
+        assert_eq!( contents, input.as_bytes() );
+    }
+
+    #[tokio::test]
+    async fn test_read_error_propagates() {
+        let reader = Trickle { input: b"abc\n".to_vec(), position: 0, calls: 0, fail_at_end: true };
+
+        let (tx,_rx) = channel::<Bytes>( 64 );
+        let read_result = read_stream( reader, tx, Arc::default(), size( 16 ) ).await;
+        assert_eq!( read_result.unwrap_err().to_string(), "connection reset" );
+
+        let (tx,_rx) = channel::<Bytes>( 64 );
+        assert!( read_input( Arc::new( FileSource::new( "does-not-exist" ) ), tx, Arc::default(), size( 16 ) ).await.is_err() );
+    }
+
+    /// Opening a socket waits for a client, which must leave the runtime
+    /// free for everything else meanwhile.
+    #[cfg(unix)]
+    #[tokio::test( flavor = "current_thread" )]
+    async fn test_open_off_runtime() {
+        use std::io::Write;
+        use std::os::unix::net::UnixStream;
+        use std::time::Duration;
+
+        let path = crate::test_support::TempPath::new( "read-socket" );
+        let ( ticked_tx, ticked_rx ) = std::sync::mpsc::channel();
+        let writer = std::thread::spawn( {
+            let path = path.to_path_buf();
+            move || {
+                // Connect once the runtime has had a turn, or it never will.
+                let ticked = ticked_rx.recv_timeout( Duration::from_secs( 5 ) ).is_ok();
+                let mut stream = loop {
+                    if let Ok( stream ) = UnixStream::connect( &path ) {
+                        break stream;
+                    }
+                    std::thread::sleep( Duration::from_millis( 10 ) );
+                };
+                stream.write_all( b"one\n" ).unwrap();
+                ticked
+            }
+        } );
+        tokio::spawn( async move {
+            tokio::time::sleep( Duration::from_millis( 50 ) ).await;
+            ticked_tx.send( () ).unwrap();
+        } );
+
+        let (tx,mut rx) = channel::<Bytes>( 64 );
+        read_input( Arc::new( crate::source::UnixSocket::new( path.to_path_buf() ) ), tx, Arc::default(), size( 16 ) ).await.unwrap();
+        assert_eq!( rx.recv().await.unwrap(), "one\n" );
+        assert!( writer.join().unwrap(), "the runtime was blocked opening the socket" );
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::{ Context, Result, anyhow };
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{ Path, PathBuf };
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::options::Options;
+use crate::solver::Solver;
+use crate::source::InputSource;
+use crate::Totals;
+
+type RunFuture<'a> = Pin<Box<dyn Future<Output = Result<Totals<String>>> + Send + 'a>>;
+type Runner = Box<dyn for<'a> Fn( &Params, &'a [Arc<dyn InputSource>], &'a Options ) -> Result<RunFuture<'a>> + Send + Sync>;
+
+/// Extra `key=value` settings handed to a solver when it is built, for
+/// puzzles that take more than the input file (day 2's cube targets).
+#[derive( Debug, Default, Clone )]
+pub struct Params {
+    values: BTreeMap<String, String>,
+}
+
+impl Params {
+    pub fn new() -> Params {
+        Params::default()
+    }
+    /// Parse `key=value` pairs as given on the command line.
+    pub fn parse<I, T>( pairs: I ) -> Result<Params>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut params = Params::new();
+        for pair in pairs {
+            let pair = pair.as_ref();
+            let ( key, value ) = pair.split_once( "=" ).context( format!( "Parameter `{pair}` is not key=value" ) )?;
+            params.values.insert( key.trim().to_string(), value.trim().to_string() );
+        }
+        Ok( params )
+    }
+    /// Set `key` to `value`, as if given on the command line.
+    pub fn insert( &mut self, key: &str, value: impl ToString ) {
+        self.values.insert( key.to_string(), value.to_string() );
+    }
+    /// The value of `key` parsed as `T`, or `default` when it was not given.
+    pub fn get_or<T>( &self, key: &str, default: T ) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        match self.values.get( key ) {
+            Some( value ) => value.parse().map_err( | err | anyhow!( "Parameter `{key}`: {err}" ) ),
+            None => Ok( default ),
+        }
+    }
+}
+
+/// One registered puzzle part.
+pub struct Entry {
+    day: u32,
+    part: u32,
+    title: &'static str,
+    input: PathBuf,
+    runner: Runner,
+}
+
+impl Entry {
+    pub fn day( &self ) -> u32 {
+        self.day
+    }
+    pub fn part( &self ) -> u32 {
+        self.part
+    }
+    pub fn title( &self ) -> &'static str {
+        self.title
+    }
+    /// The input this part reads when no other file is given.
+    pub fn input( &self ) -> &Path {
+        &self.input
+    }
+    /// Build the solver from `params` and run it over `sources`,
+    /// returning the rendered answers.
+    pub async fn run( &self, params: &Params, sources: &[Arc<dyn InputSource>], options: &Options ) -> Result<Totals<String>> {
+        ( self.runner )( params, sources, options )?.await
+    }
+}
+
+/// Every puzzle part the `aoc` binary knows how to run. Each day crate
+/// exposes a `register` function that adds its parts here.
+#[derive( Default )]
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+    /// Register `day`/`part`, built from the run's parameters by `build`
+    /// and reading `input` unless told otherwise.
+    pub fn register<S, F>( &mut self, day: u32, part: u32, title: &'static str, input: impl Into<PathBuf>, build: F )
+    where
+        S: Solver,
+        F: Fn( &Params ) -> Result<S> + Send + Sync + 'static,
+    {
+        let runner: Runner = Box::new( move | params, sources, options | {
+            let solver = build( params )?;
+            Ok( Box::pin( async move {
+                let totals = crate::run_sources( solver, sources, options ).await?;
+                Ok( Totals { per_source: totals.per_source, total: totals.total.to_string() } )
+            } ) )
+        } );
+
+        self.entries.push( Entry { day, part, title, input: input.into(), runner } );
+        self.entries.sort_by_key( | entry | ( entry.day, entry.part ) );
+    }
+    /// All registered parts, ordered by day then part.
+    pub fn entries( &self ) -> &[Entry] {
+        &self.entries
+    }
+    pub fn find( &self, day: u32, part: u32 ) -> Option<&Entry> {
+        self.entries.iter().find( | entry | entry.day == day && entry.part == part )
+    }
+}
+
+#[cfg(test)]
+mod registry_tests {
+    use super::*;
+    use crate::source::Memory;
+
+    struct Echo {
+        answer: String,
+    }
+
+    impl Solver for Echo {
+        type Record = ();
+        type Contribution = usize;
+        type Answer = String;
+
+        fn parse( &self, _line: &str ) -> Result<()> {
+            Ok( () )
+        }
+        fn contribution( &self, _record: () ) -> Result<Option<usize>> {
+            Ok( None )
+        }
+        fn initial( &self ) -> String {
+            self.answer.clone()
+        }
+        fn fold( &self, answer: String, _contribution: usize ) -> Result<String> {
+            Ok( answer )
+        }
+        fn merge( &self, answer: String, _other: String ) -> Result<String> {
+            Ok( answer )
+        }
+    }
+
+    #[test]
+    fn test_params() {
+        let params = Params::parse( [ "target_red=12", " target_blue = 14 " ] ).unwrap();
+        assert_eq!( params.get_or( "target_red", 0 ).unwrap(), 12 );
+        assert_eq!( params.get_or( "target_blue", 0 ).unwrap(), 14 );
+        assert_eq!( params.get_or( "target_green", 13 ).unwrap(), 13 );
+        assert!( params.get_or::<usize>( "target_red", 0 ).is_ok() );
+        assert!( Params::parse( [ "target_red" ] ).is_err() );
+        assert!( Params::parse( [ "red=many" ] ).unwrap().get_or( "red", 0 ).is_err() );
+    }
+
+    #[tokio::test]
+    async fn test_registry_runs_string_answers() {
+        let mut registry = Registry::new();
+        registry.register( 2, 1, "Second", "input", | params | {
+            Ok( Echo { answer: params.get_or( "answer", "none".to_string() )? } )
+        } );
+        registry.register( 1, 1, "First", "input", | _params | {
+            Ok( Echo { answer: "first".to_string() } )
+        } );
+
+        let order:Vec<(u32, u32)> = registry.entries().iter().map( | entry | ( entry.day(), entry.part() ) ).collect();
+        assert_eq!( order, vec![ ( 1, 1 ), ( 2, 1 ) ] );
+
+        let entry = registry.find( 2, 1 ).unwrap();
+        let params = Params::parse( [ "answer=forty-two" ] ).unwrap();
+        let sources:Vec<Arc<dyn InputSource>> = vec![ Arc::new( Memory::new( "a", "" ) ), Arc::new( Memory::new( "b", "" ) ) ];
+        let totals = entry.run( &params, &sources, &Options::default() ).await.unwrap();
+        assert_eq!( totals.per_source, vec![
+            ( "a".to_string(), "forty-two".to_string() ),
+            ( "b".to_string(), "forty-two".to_string() ),
+        ] );
+        assert_eq!( totals.total, "forty-two" );
+
+        assert!( registry.find( 3, 1 ).is_none() );
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{ Context, Error, Result };
+
+use std::fmt;
+use std::fs::File;
+use std::io::{ BufWriter, Write };
+use std::path::Path;
+use std::sync::{ Arc, Mutex };
+
+use crate::diagnostic;
+use crate::line::Line;
+
+/// A line the solver could not parse, fatal under `Options::strict`.
+#[derive( Debug )]
+pub struct Rejected {
+    location: String,
+    reason: String,
+    /// The line with where it went wrong pointed out.
+    rendered: String,
+}
+
+impl Rejected {
+    pub fn new( line: &Line, reason: &Error ) -> Rejected {
+        Rejected {
+            location: diagnostic::location( line, reason ),
+            reason: format!( "{reason:#}" ),
+            rendered: diagnostic::render( line, reason ),
+        }
+    }
+
+    /// The line with where it went wrong pointed out, as shown on stderr.
+    pub fn rendered( &self ) -> &str {
+        &self.rendered
+    }
+}
+
+impl fmt::Display for Rejected {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        write!( f, "{}: {}", self.location, self.reason )
+    }
+}
+
+impl std::error::Error for Rejected {}
+
+/// A file collecting every rejected line, shared by all the runs that
+/// write to it.
+#[derive( Debug, Clone )]
+pub struct Rejects {
+    file: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl Rejects {
+    pub fn create( path: &Path ) -> Result<Rejects> {
+        let file = File::create( path ).with_context( || format!( "Unable to create {}", path.display() ) )?;
+        Ok( Rejects { file: Arc::new( Mutex::new( BufWriter::new( file ) ) ) } )
+    }
+
+    /// Write one rejected line as its location, down to the column when
+    /// the reason has one, the reason and the raw line, separated by tabs.
+    /// Each is escaped so that it stays one field on one line.
+    pub fn write( &self, line: &Line, reason: &Error ) -> Result<()> {
+        let location = escape( &diagnostic::location( line, reason ) );
+        let reason = escape( &format!( "{reason:#}" ) );
+        let text = escape( &line.text );
+
+        let mut file = self.file.lock().unwrap_or_else( | poisoned | poisoned.into_inner() );
+        writeln!( file, "{location}\t{reason}\t{text}" )?;
+        Ok( () )
+    }
+
+    pub fn flush( &self ) -> Result<()> {
+        self.file.lock().unwrap_or_else( | poisoned | poisoned.into_inner() ).flush()?;
+        Ok( () )
+    }
+}
+
+/// `field` with backslashes, tabs and line breaks written as `\\`, `\t`,
+/// `\n` and `\r`.
+fn escape( field: &str ) -> String {
+    let mut escaped = String::with_capacity( field.len() );
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str( "\\\\" ),
+            '\t' => escaped.push_str( "\\t" ),
+            '\n' => escaped.push_str( "\\n" ),
+            '\r' => escaped.push_str( "\\r" ),
+            c => escaped.push( c ),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod reject_tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!( escape( "Game 1: 3 red" ), "Game 1: 3 red" );
+        assert_eq!( escape( "a\tb\rc\nd\\e" ), "a\\tb\\rc\\nd\\\\e" );
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{ Result };
+
+use std::fmt::Display;
+
+use crate::frame::Framing;
+
+/// A single puzzle part, expressed as the three steps every day shares:
+/// parse a line, turn it into a contribution, fold that into the answer.
+///
+/// Lines are parsed and turned into contributions on worker tasks, so a
+/// solver has to be shareable between threads. Folding happens on the
+/// reducer alone, in whatever order the workers finish.
+pub trait Solver: Send + Sync + 'static {
+    /// What a single line of input parses into.
+    type Record: Send + 'static;
+    /// What a parsed record adds to the answer.
+    type Contribution: Display + Send + 'static;
+    /// The final answer.
+    type Answer: Display + Send + 'static;
+
+    /// How the input is cut into the lines handed to `parse`, one per
+    /// record unless the puzzle says otherwise.
+    fn framing( &self ) -> Framing {
+        Framing::Lines
+    }
+
+    /// Parse one line, or say why it does not hold a record. Lines that
+    /// fail are skipped, or end the run under `Options::strict`.
+    fn parse( &self, line: &str ) -> Result<Self::Record>;
+
+    /// Describe a record for per-line output as named fields, such as a
+    /// game's id and maxima. Nothing beyond its contribution by default.
+    fn describe( &self, _record: &Self::Record ) -> Vec<( &'static str, String )> {
+        Vec::new()
+    }
+
+    /// Turn a record into a contribution, `None` if it does not count.
+    /// An `Overflow` here ends the run; any other error skips the line as
+    /// a failed parse does.
+    fn contribution( &self, record: Self::Record ) -> Result<Option<Self::Contribution>>;
+
+    /// The answer before any line has been folded in.
+    fn initial( &self ) -> Self::Answer;
+
+    /// Fold one contribution into the running answer, failing rather than
+    /// wrapping if it does not fit.
+    fn fold( &self, answer: Self::Answer, contribution: Self::Contribution ) -> Result<Self::Answer>;
+
+    /// Combine the answers of two separate runs, such as two input files.
+    fn merge( &self, answer: Self::Answer, other: Self::Answer ) -> Result<Self::Answer>;
+
+    /// Parse a line and produce its contribution in one go.
+    fn process_line( &self, line: &str ) -> Result<Option<Self::Contribution>> {
+        self.contribution( self.parse( line )? )
+    }
+}
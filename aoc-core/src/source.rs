@@ -0,0 +1,220 @@
+use anyhow::{ Context, Result, bail };
+
+use std::fs::File;
+use std::io::{ Cursor, Read };
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+
+use crate::decompress::Compression;
+
+/// Somewhere a run's input comes from.
+pub trait InputSource: Send + Sync {
+    /// How the source is named in reports and errors.
+    fn name( &self ) -> String;
+
+    /// Open the source for reading from the start.
+    fn open( &self ) -> Result<Box<dyn Read + Send>>;
+
+    /// How many bytes the source holds, if that is known up front.
+    fn size( &self ) -> Option<u64> {
+        None
+    }
+
+    /// The file behind the source, if it is a plain one that can be
+    /// mapped into memory.
+    fn path( &self ) -> Option<&Path> {
+        None
+    }
+}
+
+/// A file on disk.
+pub struct FileSource {
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new( path: impl Into<PathBuf> ) -> FileSource {
+        FileSource { path: path.into() }
+    }
+}
+
+impl InputSource for FileSource {
+    fn name( &self ) -> String {
+        self.path.display().to_string()
+    }
+    fn open( &self ) -> Result<Box<dyn Read + Send>> {
+        let file = File::open( &self.path ).context( format!( "Unable to open {}", self.name() ) )?;
+        Ok( Box::new( file ) )
+    }
+    /// Unknown for a pipe or FIFO, and for a compressed file, whose text
+    /// is longer than it.
+    fn size( &self ) -> Option<u64> {
+        let metadata = std::fs::metadata( &self.path ).ok()?;
+        if !metadata.is_file() || Compression::of_file( &self.path ).ok()?.is_some() {
+            return None;
+        }
+        Some( metadata.len() )
+    }
+    fn path( &self ) -> Option<&Path> {
+        Some( &self.path )
+    }
+}
+
+/// The process's standard input, given as `-`.
+pub struct Stdin;
+
+impl InputSource for Stdin {
+    fn name( &self ) -> String {
+        "<stdin>".to_string()
+    }
+    fn open( &self ) -> Result<Box<dyn Read + Send>> {
+        Ok( Box::new( std::io::stdin() ) )
+    }
+}
+
+/// Text held in memory, mostly so tests need no files on disk.
+pub struct Memory {
+    name: String,
+    text: String,
+}
+
+impl Memory {
+    pub fn new( name: &str, text: &str ) -> Memory {
+        Memory {
+            name: name.to_string(),
+            text: text.to_string(),
+        }
+    }
+}
+
+impl InputSource for Memory {
+    fn name( &self ) -> String {
+        self.name.clone()
+    }
+    fn open( &self ) -> Result<Box<dyn Read + Send>> {
+        Ok( Box::new( Cursor::new( self.text.clone().into_bytes() ) ) )
+    }
+    fn size( &self ) -> Option<u64> {
+        Some( self.text.len() as u64 )
+    }
+}
+
+/// A Unix domain socket, given as `unix:PATH`. Opening it listens on
+/// `PATH` and reads the first process to connect until it hangs up.
+#[cfg(unix)]
+pub struct UnixSocket {
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl UnixSocket {
+    pub fn new( path: impl Into<PathBuf> ) -> UnixSocket {
+        UnixSocket { path: path.into() }
+    }
+}
+
+#[cfg(unix)]
+impl InputSource for UnixSocket {
+    fn name( &self ) -> String {
+        format!( "unix:{}", self.path.display() )
+    }
+    fn open( &self ) -> Result<Box<dyn Read + Send>> {
+        use std::os::unix::net::UnixListener;
+
+        let listener = UnixListener::bind( &self.path ).context( format!( "Unable to listen on {}", self.name() ) )?;
+        let accepted = listener.accept();
+        // Nobody else is to connect, whether or not the first client did,
+        // and failing to tidy up is no reason to lose the input.
+        let _ = std::fs::remove_file( &self.path );
+
+        let ( stream, _ ) = accepted.context( format!( "Unable to accept on {}", self.name() ) )?;
+        Ok( Box::new( stream ) )
+    }
+}
+
+/// Turn one command line input into its sources: `-` for stdin,
+/// `unix:PATH` for a socket, a glob pattern for every file it matches,
+/// or else a plain path.
+pub fn parse_source( arg: &str ) -> Result<Vec<Arc<dyn InputSource>>> {
+    if arg == "-" {
+        return Ok( vec![ Arc::new( Stdin ) ] );
+    }
+
+    if let Some( path ) = arg.strip_prefix( "unix:" ) {
+        #[cfg(unix)]
+        return Ok( vec![ Arc::new( UnixSocket::new( path ) ) ] );
+        #[cfg(not(unix))]
+        bail!( "Unix sockets are not supported here: {path}" );
+    }
+
+    if arg.contains( [ '*', '?', '[' ] ) {
+        let mut sources:Vec<Arc<dyn InputSource>> = Vec::new();
+        for path in glob::glob( arg ).context( format!( "Bad pattern {arg}" ) )? {
+            sources.push( Arc::new( FileSource::new( path? ) ) );
+        }
+        if sources.is_empty() {
+            bail!( "No files match {arg}" );
+        }
+        return Ok( sources );
+    }
+
+    Ok( vec![ Arc::new( FileSource::new( arg ) ) ] )
+}
+
+#[cfg(test)]
+mod source_tests {
+    use super::*;
+
+    fn contents( source: &dyn InputSource ) -> String {
+        let mut text = String::new();
+        source.open().unwrap().read_to_string( &mut text ).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_memory() {
+        let source = Memory::new( "example", "abc\ndef\n" );
+        assert_eq!( source.name(), "example" );
+        assert_eq!( contents( &source ), "abc\ndef\n" );
+        assert_eq!( contents( &source ), "abc\ndef\n" );
+    }
+
+    #[test]
+    fn test_parse_source() {
+        assert_eq!( parse_source( "-" ).unwrap()[0].name(), "<stdin>" );
+        assert_eq!( parse_source( "unix:/tmp/aoc.sock" ).unwrap()[0].name(), "unix:/tmp/aoc.sock" );
+        assert_eq!( parse_source( "input" ).unwrap()[0].name(), "input" );
+
+        let names:Vec<String> = parse_source( "src/so*.rs" ).unwrap().iter().map( | source | source.name() ).collect();
+        assert_eq!( names, vec![ "src/solver.rs", "src/source.rs" ] );
+
+        assert!( parse_source( "src/*.nothing" ).is_err() );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket() {
+        use std::io::Write;
+        use std::os::unix::net::UnixStream;
+
+        let path = crate::test_support::TempPath::new( "socket" );
+        let source = UnixSocket::new( path.to_path_buf() );
+
+        let writer = std::thread::spawn( {
+            let path = path.to_path_buf();
+            move || {
+                let mut stream = loop {
+                    if let Ok( stream ) = UnixStream::connect( &path ) {
+                        break stream;
+                    }
+                    std::thread::sleep( std::time::Duration::from_millis( 10 ) );
+                };
+                stream.write_all( b"one\ntwo\n" ).unwrap();
+            }
+        } );
+
+        assert_eq!( contents( &source ), "one\ntwo\n" );
+        writer.join().unwrap();
+        assert!( !path.exists() );
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::Result;
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{ Path, PathBuf };
+
+use crate::number;
+use crate::solver::Solver;
+
+/// Adds up lines holding a number each, failing on a total too large for
+/// a `usize` as the puzzles' own solvers do.
+pub struct Sum;
+
+impl Solver for Sum {
+    type Record = usize;
+    type Contribution = usize;
+    type Answer = usize;
+
+    fn parse( &self, line: &str ) -> Result<usize> {
+        Ok( line.trim().parse()? )
+    }
+    fn contribution( &self, record: usize ) -> Result<Option<usize>> {
+        Ok( Some( record ) )
+    }
+    fn initial( &self ) -> usize {
+        0
+    }
+    fn fold( &self, answer: usize, contribution: usize ) -> Result<usize> {
+        Ok( number::add( answer, contribution )? )
+    }
+    fn merge( &self, answer: usize, other: usize ) -> Result<usize> {
+        Ok( number::add( answer, other )? )
+    }
+}
+
+/// A path in the temporary directory named for a test and this process,
+/// removed along with whatever the test left there once dropped, however
+/// the test ended.
+pub struct TempPath {
+    path: PathBuf,
+}
+
+impl TempPath {
+    pub fn new( name: &str ) -> TempPath {
+        TempPath { path: std::env::temp_dir().join( format!( "aoc-{name}-{}", std::process::id() ) ) }
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref( &self ) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref( &self ) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempPath {
+    fn drop( &mut self ) {
+        // Nothing may be there, if the test failed before writing it.
+        let _ = match self.path.is_dir() {
+            true => fs::remove_dir_all( &self.path ),
+            false => fs::remove_file( &self.path ),
+        };
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::{ Arc, Mutex };
+use std::time::Duration;
+
+use crate::line::Line;
+use crate::progress::{ Progress, Stage };
+
+/// How long one stage was busy over every run it was handed to, and how
+/// many items it handled.
+#[derive( Debug, Clone, Copy, PartialEq )]
+pub struct StageTiming {
+    pub stage: Stage,
+    pub busy: Duration,
+    pub items: u64,
+}
+
+impl StageTiming {
+    /// Items handled per second busy.
+    pub fn throughput( &self ) -> f64 {
+        self.items as f64 / self.busy.as_secs_f64().max( f64::EPSILON )
+    }
+}
+
+/// A line that took a worker long to parse and weigh up.
+#[derive( Debug, Clone )]
+pub struct SlowLine {
+    pub location: String,
+    pub text: String,
+    pub elapsed: Duration,
+}
+
+/// The `limit` slowest lines seen so far, slowest first.
+#[derive( Debug, Default )]
+pub struct Slowest {
+    limit: usize,
+    lines: Vec<SlowLine>,
+}
+
+impl Slowest {
+    pub fn new( limit: usize ) -> Slowest {
+        Slowest { limit, lines: Vec::new() }
+    }
+
+    pub fn note( &mut self, line: &Line, elapsed: Duration ) {
+        if self.lines.len() == self.limit && self.lines.last().is_none_or( | slowest | slowest.elapsed >= elapsed ) {
+            return;
+        }
+        let at = self.lines.partition_point( | slow | slow.elapsed >= elapsed );
+        self.lines.insert( at, SlowLine { location: line.location(), text: line.text.to_string(), elapsed } );
+        self.lines.truncate( self.limit );
+    }
+
+    fn merge( &mut self, other: Slowest ) {
+        self.lines.extend( other.lines );
+        self.lines.sort_by_key( | line | std::cmp::Reverse( line.elapsed ) );
+        self.lines.truncate( self.limit );
+    }
+}
+
+#[derive( Debug, Default )]
+struct Recorded {
+    // By `Stage`.
+    busy: [Duration; 4],
+    items: [u64; 4],
+    slowest: Slowest,
+}
+
+/// Where the time of one or more runs went, stage by stage, and the
+/// slowest lines among them.
+#[derive( Debug, Clone )]
+pub struct Timings {
+    recorded: Arc<Mutex<Recorded>>,
+    slowest: usize,
+}
+
+impl Timings {
+    /// Timings keeping the `slowest` slowest lines.
+    pub fn new( slowest: usize ) -> Timings {
+        Timings {
+            recorded: Arc::new( Mutex::new( Recorded { slowest: Slowest::new( slowest ), ..Recorded::default() } ) ),
+            slowest,
+        }
+    }
+
+    /// How many of the slowest lines are kept.
+    pub fn limit( &self ) -> usize {
+        self.slowest
+    }
+
+    /// Add the time each stage of a finished run was busy.
+    pub fn record_run( &self, progress: &Progress ) {
+        let mut recorded = self.recorded.lock().unwrap_or_else( | poisoned | poisoned.into_inner() );
+        for stage in Stage::ALL {
+            recorded.busy[stage as usize] += progress.busy( stage );
+            recorded.items[stage as usize] += progress.items( stage );
+        }
+    }
+
+    /// Add the slowest lines of a run.
+    pub fn record_lines( &self, slowest: Slowest ) {
+        let mut recorded = self.recorded.lock().unwrap_or_else( | poisoned | poisoned.into_inner() );
+        recorded.slowest.merge( slowest );
+    }
+
+    pub fn stages( &self ) -> Vec<StageTiming> {
+        let recorded = self.recorded.lock().unwrap_or_else( | poisoned | poisoned.into_inner() );
+        Stage::ALL.iter().map( | stage | StageTiming {
+            stage: *stage,
+            busy: recorded.busy[*stage as usize],
+            items: recorded.items[*stage as usize],
+        } ).collect()
+    }
+
+    pub fn slowest_lines( &self ) -> Vec<SlowLine> {
+        self.recorded.lock().unwrap_or_else( | poisoned | poisoned.into_inner() ).slowest.lines.clone()
+    }
+}
+
+#[cfg(test)]
+mod timings_tests {
+    use super::*;
+    use bytestring::ByteString;
+
+    fn line_at( number: usize ) -> Line {
+        Line { source: Arc::from( "input" ), number, byte_offset: 0, text: ByteString::from( number.to_string() ) }
+    }
+
+    #[test]
+    fn test_slowest() {
+        let timings = Timings::new( 3 );
+        for run in [ [ 5, 1, 9, 2 ], [ 7, 3, 8, 4 ] ] {
+            let mut slowest = Slowest::new( timings.limit() );
+            for ( index, millis ) in run.into_iter().enumerate() {
+                slowest.note( &line_at( index + 1 ), Duration::from_millis( millis ) );
+            }
+            timings.record_lines( slowest );
+        }
+
+        let slowest:Vec<u128> = timings.slowest_lines().iter().map( | line | line.elapsed.as_millis() ).collect();
+        assert_eq!( slowest, [ 9, 8, 7 ] );
+        assert_eq!( timings.slowest_lines()[0].location, "input:3" );
+    }
+
+    #[test]
+    fn test_record_run() {
+        let timings = Timings::new( 0 );
+        for _ in 0..2 {
+            let progress = Progress::default();
+            progress.read( 2048 );
+            progress.spent( Stage::Reader, Duration::from_millis( 1 ) );
+            timings.record_run( &progress );
+        }
+
+        let reader = timings.stages()[0];
+        assert_eq!( ( reader.stage, reader.busy, reader.items ), ( Stage::Reader, Duration::from_millis( 2 ), 4096 ) );
+        assert_eq!( reader.throughput().round(), 2_048_000.0 );
+    }
+}
@@ -0,0 +1,440 @@
+mod report;
+
+use clap::{ Parser, Subcommand };
+
+use anyhow::{ Context, Result };
+
+use aoc_core::{ Failure, Framing, InputSource, Limits, Options, Params, Registry };
+use aoc_core::fixture;
+use aoc_core::interrupt::Interrupt;
+use aoc_core::known::{ self, KnownAnswers };
+use aoc_core::reject::Rejects;
+use aoc_core::timings::Timings;
+use aoc_core::source::{ self, FileSource };
+
+use report::Format;
+
+use tokio::sync::mpsc::channel;
+
+use tracing_subscriber::filter::LevelFilter;
+
+use std::io::{ self, IsTerminal };
+use std::num::NonZeroUsize;
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+use std::process::ExitCode;
+use std::time::Instant;
+
+#[derive( Parser, Debug )]
+#[command( version, about, long_about = None )]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+    /// The most detailed diagnostics to log on stderr: off, error, warn,
+    /// info, debug or trace
+    #[arg(long, global = true, default_value_t = LevelFilter::WARN)]
+    log_level: LevelFilter,
+}
+
+#[derive( Subcommand, Debug )]
+enum Command {
+    /// Run one puzzle part, or every registered part with `--all`
+    Run( RunArgs ),
+    /// Run every registered part over its own input and check its answer
+    /// against the one already known for that input
+    Verify( VerifyArgs ),
+    /// Run every part over every example fixture of its day and print
+    /// which pass
+    Test( TestArgs ),
+    /// List every registered puzzle part
+    List,
+}
+
+#[derive( clap::Args, Debug )]
+struct RunArgs {
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    day: Option<u32>,
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    part: Option<u32>,
+    /// Run every registered part over its own input
+    #[arg(long)]
+    all: bool,
+    /// Input instead of the part's own `input`: a path, a glob, `-` for
+    /// stdin or `unix:PATH` to listen on a socket, plain or compressed
+    /// with gzip, zstd or xz. Repeat for several inputs, each answered on
+    /// its own and then in total
+    #[arg(short, long, conflicts_with = "all")]
+    file_name: Vec<String>,
+    /// Replace invalid UTF-8 in the input with U+FFFD instead of failing
+    #[arg(long)]
+    lossy: bool,
+    /// How to cut the input into records: lines, paragraphs, fixed:N or
+    /// delim:STRING, instead of the solver's own choice
+    #[arg(long)]
+    framing: Option<Framing>,
+    /// Fail on any record longer than this many bytes
+    #[arg(long)]
+    max_line_length: Option<usize>,
+    /// Fail on input of more than this many records
+    #[arg(long)]
+    max_lines: Option<usize>,
+    /// Fail on input of more than this many bytes
+    #[arg(long)]
+    max_bytes: Option<usize>,
+    /// Lines processed at once, one per CPU by default; 1 processes them
+    /// in order on a single worker
+    #[arg(short, long)]
+    jobs: Option<NonZeroUsize>,
+    /// How many chunks or lines each stage may queue for the next before
+    /// waiting for it
+    #[arg(long)]
+    capacity: Option<NonZeroUsize>,
+    /// The most bytes to read from an input at once, 65536 by default
+    #[arg(long, value_name = "BYTES")]
+    buffer_size: Option<NonZeroUsize>,
+    /// Map input files into memory and split them into a range of lines
+    /// per job, each processed on a thread of its own. Inputs that cannot
+    /// be split, such as stdin or under --lines, are streamed as usual
+    #[arg(long)]
+    mmap: bool,
+    /// Fail on the first line that does not parse, instead of skipping it
+    #[arg(long)]
+    strict: bool,
+    /// Write every line that does not parse to this file, with its
+    /// location and the reason, separated by tabs
+    #[arg(long)]
+    rejects: Option<PathBuf>,
+    /// Print what came of every line: its value, how the solver saw it or
+    /// why it was skipped
+    #[arg(long)]
+    lines: bool,
+    /// Print every line's outcome in input order, however many jobs run
+    #[arg(long)]
+    ordered: bool,
+    /// How to print answers and lines
+    #[arg(long, value_enum, default_value_t = Format::Plain)]
+    format: Format,
+    /// Leave out banners such as "That's all she wrote...", the summary
+    /// of skipped lines and the line a --strict run failed on
+    #[arg(short, long)]
+    quiet: bool,
+    /// Fail unless the answer, or the total over several inputs, is this
+    #[arg(long, conflicts_with = "all")]
+    expect: Option<String>,
+    /// Show bytes read, lines framed and processed, throughput and ETA on
+    /// stderr while each input is run, if stderr is a terminal
+    #[arg(long)]
+    progress: bool,
+    /// After the answers, show on stderr how long each stage was busy and
+    /// the N slowest lines, 5 unless given
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "5")]
+    timings: Option<usize>,
+    /// Add answers up without bound instead of failing once they are too
+    /// large for a usize, for stress inputs; the same as `-p big=true`
+    #[arg(long)]
+    big: bool,
+    /// Extra solver settings as key=value, e.g. `-p target_red=12`
+    #[arg(short, long = "param")]
+    params: Vec<String>,
+}
+
+#[derive( clap::Args, Debug )]
+struct VerifyArgs {
+    /// The known answers, keyed by day, part and input hash
+    #[arg(long, default_value_os_t = workspace().join( "answers.tsv" ))]
+    answers: PathBuf,
+    /// Store the answer of every part whose input has none yet
+    #[arg(long)]
+    record: bool,
+    /// Lines processed at once, one per CPU by default
+    #[arg(short, long)]
+    jobs: Option<NonZeroUsize>,
+}
+
+#[derive( clap::Args, Debug )]
+struct TestArgs {
+    /// The fixtures, as `dayN/NAME.txt` with their answers in
+    /// `dayN/NAME.expected`
+    #[arg(long, default_value_os_t = workspace().join( "fixtures" ))]
+    fixtures: PathBuf,
+    /// Lines processed at once, one per CPU by default
+    #[arg(short, long)]
+    jobs: Option<NonZeroUsize>,
+}
+
+/// The root of the workspace, where the known answers and fixtures live.
+fn workspace() -> PathBuf {
+    Path::new( env!( "CARGO_MANIFEST_DIR" ) ).parent().unwrap_or( Path::new( "." ) ).to_path_buf()
+}
+
+fn registry() -> Registry {
+    let mut registry = Registry::new();
+    day1_pt1::register( &mut registry );
+    day1_pt2::register( &mut registry );
+    day2_pt1::register( &mut registry );
+    day2_pt2::register( &mut registry );
+    registry
+}
+
+async fn run( registry: &Registry, args: RunArgs ) -> Result<ExitCode> {
+    let mut params = Params::parse( &args.params )?;
+    if args.big {
+        params.insert( "big", true );
+    }
+    let mut options = Options {
+        lossy: args.lossy,
+        framing: args.framing,
+        limits: Limits {
+            max_line_length: args.max_line_length,
+            max_lines: args.max_lines,
+            max_bytes: args.max_bytes,
+        },
+        jobs: args.jobs,
+        capacity: args.capacity,
+        buffer_size: args.buffer_size,
+        mapped: args.mmap,
+        strict: args.strict,
+        rejects: args.rejects.as_deref().map( Rejects::create ).transpose()?,
+        ordered: args.ordered,
+        quiet: args.quiet,
+        progress: args.progress,
+        interrupt: Interrupt::on_ctrl_c(),
+        ..Options::default()
+    };
+
+    let entries = match ( args.day, args.part ) {
+        ( Some( day ), Some( part ) ) => {
+            vec![ registry.find( day, part ).context( format!( "No solver registered for day {day} part {part}" ) )? ]
+        },
+        _ => registry.entries().iter().collect(),
+    };
+
+    let mut sources:Vec<Arc<dyn InputSource>> = Vec::new();
+    for file_name in &args.file_name {
+        sources.extend( source::parse_source( file_name )? );
+    }
+
+    report::print_header( args.format )?;
+
+    let mut rows = Vec::new();
+    let mut lines = Vec::new();
+    let mut timings = Vec::new();
+
+    for entry in entries {
+        let default_source:Vec<Arc<dyn InputSource>> = vec![ Arc::new( FileSource::new( entry.input() ) ) ];
+        let sources = if sources.is_empty() { &default_source } else { &sources };
+
+        // Each part's lines go to a printer of their own, done once the
+        // part is.
+        let printer = ( args.lines || args.ordered ).then( || {
+            let (tx,rx) = channel( options.capacity().get() );
+            options.lines = Some( tx );
+            tokio::spawn( report::print_outcomes( args.format, ( entry.day(), entry.part() ), rx ) )
+        } );
+
+        options.timings = args.timings.map( Timings::new );
+        if let Some( entry_timings ) = &options.timings {
+            timings.push( ( entry, entry_timings.clone() ) );
+        }
+
+        let start = Instant::now();
+        let answer = entry.run( &params, sources, &options ).await;
+
+        options.lines = None;
+        if let Some( printer ) = printer {
+            lines.extend( printer.await?? );
+        }
+
+        let answer = match &args.expect {
+            Some( expected ) => answer.and_then( | mut totals | {
+                totals.total = known::check( expected, totals.total )?;
+                Ok( totals )
+            } ),
+            None => answer,
+        };
+
+        match answer {
+            Ok( totals ) if sources.len() > 1 => {
+                for ( name, answer ) in totals.per_source {
+                    rows.push( report::Row::new( entry, name, Ok( answer ), None ) );
+                }
+                rows.push( report::Row::new( entry, "total".to_string(), Ok( totals.total ), Some( start.elapsed() ) ) );
+            },
+            Ok( totals ) => {
+                rows.push( report::Row::new( entry, sources[0].name(), Ok( totals.total ), Some( start.elapsed() ) ) );
+            },
+            Err( err ) => {
+                let name = if sources.len() > 1 { "total".to_string() } else { sources[0].name() };
+                rows.push( report::Row::new( entry, name, Err( err ), Some( start.elapsed() ) ) );
+            },
+        }
+
+        // Parts not yet begun are left out, rather than each interrupted
+        // in turn.
+        if options.interrupt.is_triggered() {
+            break;
+        }
+    }
+
+    report::print_answers( args.format, &rows, lines )?;
+    for ( entry, entry_timings ) in timings {
+        report::print_timings( entry, &entry_timings );
+    }
+
+    match report::print_errors( &rows ) {
+        Some( failure ) => Ok( ExitCode::from( failure.exit_code() ) ),
+        None => Ok( ExitCode::SUCCESS ),
+    }
+}
+
+/// Run every registered part over its own input and compare its answer
+/// with the known one, storing it under `--record` if there is none.
+async fn verify( registry: &Registry, args: VerifyArgs ) -> Result<ExitCode> {
+    let mut known = KnownAnswers::load( &args.answers )?;
+    let options = Options { jobs: args.jobs, quiet: true, ..Options::default() };
+
+    let mut verdicts = Vec::new();
+    for entry in registry.entries() {
+        let sources:Vec<Arc<dyn InputSource>> = vec![ Arc::new( FileSource::new( entry.input() ) ) ];
+        let verdict = match known::input_hash( entry.input() ) {
+            Ok( hash ) => {
+                let expected = known.get( entry.day(), entry.part(), &hash ).map( str::to_string );
+                let answer = entry.run( &Params::new(), &sources, &options ).await.map( | totals | totals.total );
+                match ( &expected, &answer ) {
+                    ( None, Ok( answer ) ) if args.record => {
+                        known.insert( entry.day(), entry.part(), hash, answer.clone() );
+                        report::Verdict::new( entry, expected, Ok( answer.clone() ) ).recorded()
+                    },
+                    _ => report::Verdict::new( entry, expected, answer ),
+                }
+            },
+            Err( err ) => {
+                let err = anyhow::Error::from( err ).context( format!( "Unable to read {}", entry.input().display() ) );
+                report::Verdict::new( entry, None, Err( err ) )
+            },
+        };
+        verdicts.push( verdict );
+    }
+
+    if args.record {
+        known.save()?;
+    }
+
+    match report::print_verdicts( &verdicts ) {
+        Some( failure ) => Ok( ExitCode::from( failure.exit_code() ) ),
+        None => Ok( ExitCode::SUCCESS ),
+    }
+}
+
+/// Run every part over every fixture of its day, checking its answer
+/// where the fixture has one.
+async fn test( registry: &Registry, args: TestArgs ) -> Result<ExitCode> {
+    let options = Options { jobs: args.jobs, quiet: true, ..Options::default() };
+
+    let mut cases = Vec::new();
+    for fixture in fixture::fixtures( &args.fixtures )? {
+        let sources:Vec<Arc<dyn InputSource>> = vec![ Arc::new( FileSource::new( fixture.input() ) ) ];
+        for entry in registry.entries().iter().filter( | entry | entry.day() == fixture.day() ) {
+            let expected = fixture.expected( entry.part() ).map( str::to_string );
+            let answer = entry.run( &Params::new(), &sources, &options ).await.map( | totals | totals.total );
+            let name = format!( "day{}/{}", fixture.day(), fixture.name() );
+            cases.push( ( name, report::Verdict::new( entry, expected, answer ) ) );
+        }
+    }
+
+    match report::print_matrix( &cases ) {
+        Some( failure ) => Ok( ExitCode::from( failure.exit_code() ) ),
+        None => Ok( ExitCode::SUCCESS ),
+    }
+}
+
+/// Exits 0 when every part succeeded, or with the status of the most
+/// serious failure, as listed on `Failure`.
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    tracing_subscriber::fmt()
+        .with_max_level( args.log_level )
+        .with_writer( io::stderr )
+        .with_ansi( io::stderr().is_terminal() )
+        .init();
+
+    let registry = registry();
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok( runtime ) => runtime,
+        Err( err ) => {
+            eprintln!( "Error: Unable to start the runtime: {err}" );
+            return ExitCode::from( Failure::Other.exit_code() );
+        },
+    };
+
+    let result = runtime.block_on( async {
+        match args.command {
+            Command::Run( run_args ) => run( &registry, run_args ).await,
+            Command::Verify( verify_args ) => verify( &registry, verify_args ).await,
+            Command::Test( test_args ) => test( &registry, test_args ).await,
+            Command::List => {
+                report::print_list( &registry );
+                Ok( ExitCode::SUCCESS )
+            },
+        }
+    } );
+
+    // After an interrupt a read of stdin or a socket may still be waiting
+    // on the blocking pool, and would hold the process open until more
+    // input came, so the runtime is left behind rather than waited for.
+    runtime.shutdown_background();
+
+    result.unwrap_or_else( | err | {
+        eprintln!( "Error: {err:#}" );
+        ExitCode::from( Failure::of( &err ).exit_code() )
+    } )
+}
+
+#[cfg(test)]
+mod main_tests {
+    use super::*;
+    use aoc_core::source::Memory;
+
+    #[tokio::test]
+    async fn test_fixtures_pass() {
+        let args = TestArgs { fixtures: workspace().join( "fixtures" ), jobs: None };
+        assert_eq!( test( &registry(), args ).await.unwrap(), ExitCode::SUCCESS );
+    }
+
+    #[tokio::test]
+    async fn test_too_many_cubes() {
+        let registry = registry();
+        let sources:Vec<Arc<dyn InputSource>> = vec![ Arc::new( Memory::new( "input", "Game 1: 18446744073709551615 red, 1 red\n" ) ) ];
+        let options = Options { quiet: true, ..Options::default() };
+
+        for part in [ 1, 2 ] {
+            let entry = registry.find( 2, part ).unwrap();
+            let err = entry.run( &Params::new(), &sources, &options ).await.err().unwrap();
+            assert_eq!( Failure::of( &err ), Failure::Parse );
+            assert!( format!( "{err:#}" ).contains( "input:1: too many red cubes: 18446744073709551615 + 1 is too large" ), "{err:#}" );
+        }
+
+        let big = Params::parse( [ "big=true" ] ).unwrap();
+        let totals = registry.find( 2, 2 ).unwrap().run( &big, &sources, &options ).await.unwrap();
+        assert_eq!( totals.total, "0" );
+    }
+
+    #[tokio::test]
+    async fn test_mapped_matches_streaming() {
+        let registry = registry();
+        let params = Params::new();
+        for entry in registry.entries() {
+            let sources:Vec<Arc<dyn InputSource>> = vec![ Arc::new( FileSource::new( entry.input() ) ) ];
+            let streaming = Options { quiet: true, ..Options::default() };
+            let streamed = entry.run( &params, &sources, &streaming ).await.unwrap().total;
+
+            for jobs in [ 1, 2, 5, 16 ] {
+                let mapped = Options { mapped: true, jobs: NonZeroUsize::new( jobs ), quiet: true, ..Options::default() };
+                let answer = entry.run( &params, &sources, &mapped ).await.unwrap().total;
+                assert_eq!( answer, streamed, "day {} part {} on {jobs} jobs", entry.day(), entry.part() );
+            }
+        }
+    }
+}
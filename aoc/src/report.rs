@@ -0,0 +1,448 @@
+use anyhow::{ Result };
+
+use aoc_core::{ Failure, Outcome };
+use aoc_core::interrupt::Interrupted;
+use aoc_core::known;
+use aoc_core::progress::Stage;
+use aoc_core::timings::Timings;
+use aoc_core::registry::{ Entry, Registry };
+
+use clap::ValueEnum;
+
+use serde_json::{ Map, Value, json };
+
+use tokio::sync::mpsc::Receiver;
+
+use std::io;
+use std::time::Duration;
+
+/// How answers, and the outcome of every line with `--lines`, are printed.
+#[derive( ValueEnum, Debug, Clone, Copy, PartialEq )]
+pub enum Format {
+    /// A table of answers, and a line of text per line of input
+    Plain,
+    /// One JSON document holding the answers and lines
+    Json,
+    /// A JSON object per line and per answer
+    Jsonl,
+    /// A record per line and per answer, comma separated
+    Csv,
+    /// A record per line and per answer, tab separated
+    Tsv,
+}
+
+/// Columns of the csv and tsv formats. Line records leave the answer
+/// columns empty, answer records the line columns.
+const COLUMNS:[&str; 12] = [ "kind", "day", "part", "input", "line", "text", "value", "skipped", "fields", "answer", "error", "time_ms" ];
+
+/// The outcome of running one puzzle part over one input, or the total
+/// over several of them.
+pub struct Row {
+    day: u32,
+    part: u32,
+    title: &'static str,
+    input: String,
+    answer: Result<String>,
+    elapsed: Option<Duration>,
+}
+
+impl Row {
+    pub fn new( entry: &Entry, input: String, answer: Result<String>, elapsed: Option<Duration> ) -> Row {
+        Row {
+            day: entry.day(),
+            part: entry.part(),
+            title: entry.title(),
+            input,
+            answer,
+            elapsed,
+        }
+    }
+    fn error( &self ) -> Option<&anyhow::Error> {
+        self.answer.as_ref().err()
+    }
+    fn json( &self ) -> Value {
+        json!( {
+            "kind": "answer",
+            "day": self.day,
+            "part": self.part,
+            "title": self.title,
+            "input": self.input,
+            "answer": self.answer.as_ref().ok().map( String::as_str ).or_else( || Some( Interrupted::find( self.error()? )?.answer() ) ),
+            "error": self.error().map( | err | format!( "{err:#}" ) ),
+            "time_ms": self.elapsed.map( | elapsed | elapsed.as_secs_f64() * 1000.0 ),
+        } )
+    }
+    fn cells( &self ) -> [String; 6] {
+        let answer = match &self.answer {
+            Ok( answer ) => answer.clone(),
+            Err( err ) => match Interrupted::find( err ) {
+                Some( interrupted ) => format!( "{} (interrupted after {} lines)", interrupted.answer(), interrupted.lines() ),
+                None => format!( "error: {err:#}" ),
+            },
+        };
+        [
+            self.day.to_string(),
+            self.part.to_string(),
+            self.title.to_string(),
+            self.input.clone(),
+            answer,
+            self.elapsed.map( | elapsed | format!( "{elapsed:.2?}" ) ).unwrap_or_default(),
+        ]
+    }
+}
+
+fn print_rows<R: AsRef<[String]>>( header: &[&str], rows: &[R] ) {
+    for line in table( header, rows ) {
+        println!( "{line}" );
+    }
+}
+
+/// Lay `rows` out in columns under `header`.
+fn table<R: AsRef<[String]>>( header: &[&str], rows: &[R] ) -> Vec<String> {
+    let mut widths:Vec<usize> = header.iter().map( | cell | cell.len() ).collect();
+    for row in rows {
+        for ( width, cell ) in widths.iter_mut().zip( row.as_ref() ) {
+            *width = (*width).max( cell.chars().count() );
+        }
+    }
+
+    let line = | cells: Vec<&str> | {
+        let padded:Vec<String> = cells.iter().zip( &widths ).map( | ( cell, width ) | format!( "{cell:<width$}" ) ).collect();
+        padded.join( " | " ).trim_end().to_string()
+    };
+
+    let mut lines = vec![ line( header.to_vec() ) ];
+    let rules:Vec<String> = widths.iter().map( | width | "-".repeat( *width ) ).collect();
+    lines.push( rules.join( "-+-" ) );
+    for row in rows {
+        lines.push( line( row.as_ref().iter().map( | cell | cell.as_str() ).collect() ) );
+    }
+    lines
+}
+
+/// Print the answer and wall time of every part that was run, after the
+/// outcome of every line in `lines` in the json format.
+pub fn print_answers( format: Format, rows: &[Row], lines: Vec<Value> ) -> Result<()> {
+    match format {
+        Format::Plain => print_table( rows ),
+        Format::Json => {
+            let answers:Vec<Value> = rows.iter().map( Row::json ).collect();
+            println!( "{:#}", json!( { "answers": answers, "lines": lines } ) );
+        },
+        Format::Jsonl | Format::Csv | Format::Tsv => {
+            for row in rows {
+                print_record( format, &row.json() )?;
+            }
+        },
+    }
+    Ok( () )
+}
+
+/// Print the header of the csv and tsv formats.
+pub fn print_header( format: Format ) -> Result<()> {
+    if let Some( delimiter ) = delimiter( format ) {
+        let mut writer = csv::WriterBuilder::new().delimiter( delimiter ).from_writer( io::stdout() );
+        writer.write_record( COLUMNS )?;
+        writer.flush()?;
+    }
+    Ok( () )
+}
+
+fn delimiter( format: Format ) -> Option<u8> {
+    match format {
+        Format::Csv => Some( b',' ),
+        Format::Tsv => Some( b'\t' ),
+        _ => None,
+    }
+}
+
+/// Print one line or answer `object` as a jsonl line or a csv or tsv
+/// record.
+fn print_record( format: Format, object: &Value ) -> Result<()> {
+    let Some( delimiter ) = delimiter( format ) else {
+        println!( "{object}" );
+        return Ok( () );
+    };
+
+    let cells = COLUMNS.map( | column | match &object[column] {
+        Value::Null => String::new(),
+        Value::String( text ) => text.clone(),
+        Value::Object( fields ) => {
+            let fields:Vec<String> = fields.iter().map( | ( name, value ) | format!( "{name}={}", value.as_str().unwrap_or_default() ) ).collect();
+            fields.join( " " )
+        },
+        value => value.to_string(),
+    } );
+    let mut writer = csv::WriterBuilder::new().delimiter( delimiter ).has_headers( false ).from_writer( io::stdout() );
+    writer.write_record( cells )?;
+    writer.flush()?;
+    Ok( () )
+}
+
+fn print_table( rows: &[Row] ) {
+    let cells:Vec<[String; 6]> = rows.iter().map( Row::cells ).collect();
+    print_rows( &[ "Day", "Part", "Title", "Input", "Answer", "Time" ], &cells );
+}
+
+/// Summarise every failed part on stderr, handing back the most serious
+/// failure among them.
+pub fn print_errors( rows: &[Row] ) -> Option<Failure> {
+    let failed:Vec<( &Row, &anyhow::Error )> = rows.iter().filter_map( | row | Some( ( row, row.error()? ) ) ).collect();
+    if failed.is_empty() {
+        return None;
+    }
+
+    eprintln!( "{} of {} parts failed:", failed.len(), rows.len() );
+    let mut worst = Failure::Other;
+    for ( row, err ) in failed {
+        let failure = Failure::of( err );
+        eprintln!( "  day {} part {} ({}): {failure:?} error: {err:#}", row.day, row.part, row.input );
+        worst = worst.max( failure );
+    }
+    Some( worst )
+}
+
+/// How one part's answer over its own input compares with the known one.
+pub struct Verdict {
+    day: u32,
+    part: u32,
+    title: &'static str,
+    expected: Option<String>,
+    answer: Result<String>,
+    recorded: bool,
+}
+
+impl Verdict {
+    pub fn new( entry: &Entry, expected: Option<String>, answer: Result<String> ) -> Verdict {
+        Verdict {
+            day: entry.day(),
+            part: entry.part(),
+            title: entry.title(),
+            expected,
+            answer,
+            recorded: false,
+        }
+    }
+    /// Mark the answer as newly stored, there being none to compare with.
+    pub fn recorded( self ) -> Verdict {
+        Verdict { recorded: true, ..self }
+    }
+    /// How the part failed, if it did: its run did, or it got the wrong
+    /// answer.
+    fn failure( &self ) -> Option<( Failure, String )> {
+        let err = match ( &self.expected, &self.answer ) {
+            ( _, Err( err ) ) => return Some( ( Failure::of( err ), format!( "{err:#}" ) ) ),
+            ( Some( expected ), Ok( answer ) ) => known::check( expected, answer.clone() ).err()?,
+            ( None, Ok( _ ) ) => return None,
+        };
+        Some( ( Failure::of( &err ), err.to_string() ) )
+    }
+    fn result( &self ) -> &'static str {
+        match ( &self.expected, &self.answer ) {
+            ( _, Err( _ ) ) => "FAIL",
+            ( Some( expected ), Ok( answer ) ) if expected != answer => "FAIL",
+            ( Some( _ ), Ok( _ ) ) => "pass",
+            ( None, Ok( _ ) ) if self.recorded => "recorded",
+            ( None, Ok( _ ) ) => "unknown",
+        }
+    }
+    fn cells( &self ) -> [String; 6] {
+        let answer = match &self.answer {
+            Ok( answer ) => answer.clone(),
+            Err( err ) => format!( "error: {err:#}" ),
+        };
+        [
+            self.day.to_string(),
+            self.part.to_string(),
+            self.title.to_string(),
+            self.expected.clone().unwrap_or_else( || "-".to_string() ),
+            answer,
+            self.result().to_string(),
+        ]
+    }
+}
+
+/// Print whether every part still gets its known answer, and summarise
+/// the ones that do not on stderr, handing back the most serious failure
+/// among them.
+pub fn print_verdicts( verdicts: &[Verdict] ) -> Option<Failure> {
+    let cells:Vec<[String; 6]> = verdicts.iter().map( Verdict::cells ).collect();
+    print_rows( &[ "Day", "Part", "Title", "Expected", "Answer", "Result" ], &cells );
+
+    let failed = verdicts.iter().filter_map( | verdict | {
+        Some( ( format!( "day {} part {}", verdict.day, verdict.part ), verdict.failure()? ) )
+    } );
+    print_failures( failed, verdicts.len(), "parts" )
+}
+
+/// Print which parts pass over which fixture, a row per fixture and a
+/// column per part, and summarise the failures on stderr, handing back
+/// the most serious of them.
+///
+/// ```text
+/// Fixture          | Part 1 | Part 2
+/// -----------------+--------+-------
+/// day1/calibration | pass   | pass
+/// day1/spelled     | 209    | pass
+/// ```
+///
+/// A part with no answer to give shows the one it got.
+pub fn print_matrix( cases: &[( String, Verdict )] ) -> Option<Failure> {
+    let parts = cases.iter().map( | ( _, verdict ) | verdict.part ).max().unwrap_or( 0 );
+    let mut header = vec![ "Fixture".to_string() ];
+    header.extend( ( 1..=parts ).map( | part | format!( "Part {part}" ) ) );
+
+    let mut cells:Vec<Vec<String>> = Vec::new();
+    for ( fixture, verdict ) in cases {
+        if cells.last().is_none_or( | row | row[0] != *fixture ) {
+            let mut row = vec![ fixture.clone() ];
+            row.resize( parts as usize + 1, String::new() );
+            cells.push( row );
+        }
+        let cell = match ( verdict.result(), &verdict.answer ) {
+            ( "unknown", Ok( answer ) ) => answer.clone(),
+            ( result, _ ) => result.to_string(),
+        };
+        if let Some( row ) = cells.last_mut() {
+            row[verdict.part as usize] = cell;
+        }
+    }
+    let header:Vec<&str> = header.iter().map( String::as_str ).collect();
+    print_rows( &header, &cells );
+
+    let failed = cases.iter().filter_map( | ( fixture, verdict ) | {
+        Some( ( format!( "{fixture} part {}", verdict.part ), verdict.failure()? ) )
+    } );
+    print_failures( failed, cases.len(), "cases" )
+}
+
+/// Summarise every `failed` one of `total` things on stderr, handing back
+/// the most serious failure among them.
+fn print_failures( failed: impl Iterator<Item = ( String, ( Failure, String ) )>, total: usize, what: &str ) -> Option<Failure> {
+    let failed:Vec<_> = failed.collect();
+    if failed.is_empty() {
+        return None;
+    }
+
+    eprintln!( "{} of {total} {what} failed:", failed.len() );
+    let mut worst = Failure::Other;
+    for ( name, ( failure, err ) ) in failed {
+        eprintln!( "  {name}: {failure:?} error: {err}" );
+        worst = worst.max( failure );
+    }
+    Some( worst )
+}
+
+/// Print the outcome of every line of `entry` as it arrives, in the plain
+/// format as:
+///
+/// ```text
+/// input:1: 12
+/// input:2: skipped: No digit in line
+/// input:3: - (id=3, red=20, green=8, blue=6)
+/// ```
+///
+/// The json format prints nothing here: the outcomes are handed back to go
+/// in the document with the answers.
+pub async fn print_outcomes( format: Format, entry: ( u32, u32 ), mut rx: Receiver<Outcome> ) -> Result<Vec<Value>> {
+    let mut lines = Vec::new();
+
+    while let Some( outcome ) = rx.recv().await {
+        match format {
+            Format::Plain => print_outcome( &outcome ),
+            Format::Json => lines.push( outcome_json( entry, &outcome ) ),
+            _ => print_record( format, &outcome_json( entry, &outcome ) )?,
+        }
+    }
+
+    Ok( lines )
+}
+
+fn print_outcome( outcome: &Outcome ) {
+    let result = match ( &outcome.skipped, &outcome.value ) {
+        ( Some( reason ), _ ) => format!( "skipped: {reason}" ),
+        ( None, Some( value ) ) => value.clone(),
+        ( None, None ) => "-".to_string(),
+    };
+    let fields:Vec<String> = outcome.fields.iter().map( | ( name, value ) | format!( "{name}={value}" ) ).collect();
+
+    if fields.is_empty() {
+        println!( "{}: {result}", outcome.line.location() );
+    } else {
+        println!( "{}: {result} ({})", outcome.line.location(), fields.join( ", " ) );
+    }
+}
+
+fn outcome_json( ( day, part ): ( u32, u32 ), outcome: &Outcome ) -> Value {
+    let fields:Map<String, Value> = outcome.fields.iter()
+        .map( | ( name, value ) | ( name.to_string(), Value::String( value.clone() ) ) )
+        .collect();
+    json!( {
+        "kind": "line",
+        "day": day,
+        "part": part,
+        "input": &*outcome.line.source,
+        "line": outcome.line.number,
+        "text": &*outcome.line.text,
+        "value": outcome.value,
+        "skipped": outcome.skipped,
+        "fields": fields,
+    } )
+}
+
+/// Print on stderr how long each stage of `entry`'s runs was busy, and
+/// its slowest lines:
+///
+/// ```text
+/// Timings for day 1 part 1:
+/// Stage   | Busy     | Items         | Throughput
+/// --------+----------+---------------+------------------
+/// reader  | 1.21ms   | 21813 bytes   | 17.2 MiB/s
+/// framer  | 402.51µs | 1001 lines    | 2486.9k lines/s
+/// ...
+/// Slowest lines:
+///   input:412: 8.93µs eightwothree7
+/// ```
+///
+/// Workers are busy at once, so theirs is the time of them all added up.
+pub fn print_timings( entry: &Entry, timings: &Timings ) {
+    let cells:Vec<[String; 4]> = timings.stages().iter().map( | timing | {
+        let ( items, throughput ) = match timing.stage {
+            Stage::Reader => (
+                format!( "{} bytes", timing.items ),
+                format!( "{:.1} MiB/s", timing.throughput() / ( 1024.0 * 1024.0 ) ),
+            ),
+            _ => (
+                format!( "{} lines", timing.items ),
+                format!( "{:.1}k lines/s", timing.throughput() / 1000.0 ),
+            ),
+        };
+        [ timing.stage.name().to_string(), format!( "{:.2?}", timing.busy ), items, throughput ]
+    } ).collect();
+
+    eprintln!( "Timings for day {} part {}:", entry.day(), entry.part() );
+    for line in table( &[ "Stage", "Busy", "Items", "Throughput" ], &cells ) {
+        eprintln!( "{line}" );
+    }
+
+    let slowest = timings.slowest_lines();
+    if !slowest.is_empty() {
+        eprintln!( "Slowest lines:" );
+        for line in slowest {
+            eprintln!( "  {}: {:.2?} {}", line.location, line.elapsed, line.text );
+        }
+    }
+}
+
+/// Print every registered part and the input it reads by default.
+pub fn print_list( registry: &Registry ) {
+    let cells:Vec<[String; 4]> = registry.entries().iter().map( | entry | {
+        [
+            entry.day().to_string(),
+            entry.part().to_string(),
+            entry.title().to_string(),
+            entry.input().display().to_string(),
+        ]
+    } ).collect();
+    print_rows( &[ "Day", "Part", "Title", "Input" ], &cells );
+}
@@ -0,0 +1,53 @@
+use anyhow::{ Context, Result };
+
+use aoc_core::{ Registry, Solver };
+use aoc_core::number::{ Arithmetic, Number };
+
+fn process_line( line: &str ) -> Option<usize> {
+    let mut digits:Vec<char> = Vec::new();
+
+    for c in line.chars() {
+        if c.is_ascii_digit() {
+            digits.push( c );
+        }
+    }
+
+    let first_digit:char = *digits.first()?;
+    let final_digit:char = *digits.last()?;
+
+    let string:String = format!( "{}{}", first_digit, final_digit);
+
+    string.parse::<usize>().ok()
+}
+
+struct Calibration {
+    arithmetic: Arithmetic,
+}
+
+impl Solver for Calibration {
+    type Record = usize;
+    type Contribution = Number;
+    type Answer = Number;
+
+    fn parse( &self, line: &str ) -> Result<usize> {
+        process_line( line ).context( "No digit in line" )
+    }
+    fn contribution( &self, record: usize ) -> Result<Option<Number>> {
+        Ok( Some( self.arithmetic.number( record ) ) )
+    }
+    fn initial( &self ) -> Number {
+        self.arithmetic.number( 0 )
+    }
+    fn fold( &self, answer: Number, contribution: Number ) -> Result<Number> {
+        Ok( answer.checked_add( contribution )? )
+    }
+    fn merge( &self, answer: Number, other: Number ) -> Result<Number> {
+        Ok( answer.checked_add( other )? )
+    }
+}
+
+pub fn register( registry: &mut Registry ) {
+    registry.register( 1, 1, "Trebuchet?!", concat!( env!( "CARGO_MANIFEST_DIR" ), "/input" ), | params | {
+        Ok( Calibration { arithmetic: Arithmetic::from_params( params )? } )
+    } );
+}
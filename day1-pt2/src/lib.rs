@@ -0,0 +1,10 @@
+mod processor;
+
+use aoc_core::Registry;
+use aoc_core::number::Arithmetic;
+
+pub fn register( registry: &mut Registry ) {
+    registry.register( 1, 2, "Trebuchet?!", concat!( env!( "CARGO_MANIFEST_DIR" ), "/input" ), | params | {
+        Ok( processor::Calibration { arithmetic: Arithmetic::from_params( params )? } )
+    } );
+}
@@ -1,7 +1,7 @@
-use anyhow::{ Result };
-use tokio::sync::mpsc::{ UnboundedSender, UnboundedReceiver };
+use anyhow::{ Context, Result };
 
-use tokio::task::JoinSet;
+use aoc_core::Solver;
+use aoc_core::number::{ Arithmetic, Number };
 
 const NUMBER_TO_CHAR:[( &str, char);9] = [
     (   "one", '1'),
@@ -15,7 +15,7 @@ const NUMBER_TO_CHAR:[( &str, char);9] = [
     (  "nine", '9'),
 ];
 
-async fn process_line( line:String ) -> Option<usize> {
+fn process_line( line: &str ) -> Option<usize> {
     let mut tokens:Vec<(usize, char)> = Vec::new();
 
     for ( string, number) in NUMBER_TO_CHAR {
@@ -26,8 +26,8 @@ async fn process_line( line:String ) -> Option<usize> {
         }
     }
 
-    for ( index, c) in line.chars().enumerate() {
-        if c.is_digit(10) {
+    for ( index, c) in line.char_indices() {
+        if c.is_ascii_digit() {
             tokens.push( ( index, c ) );
         }
     }
@@ -40,21 +40,47 @@ async fn process_line( line:String ) -> Option<usize> {
     let string:String = format!( "{}{}", first_digit, final_digit);
 
 
-    Some(string.parse::<usize>().ok()?)
+    string.parse::<usize>().ok()
+}
+
+pub struct Calibration {
+    pub arithmetic: Arithmetic,
+}
+
+impl Solver for Calibration {
+    type Record = usize;
+    type Contribution = Number;
+    type Answer = Number;
+
+    fn parse( &self, line: &str ) -> Result<usize> {
+        process_line( line ).context( "No digit, in figures or words, in line" )
+    }
+    fn contribution( &self, record: usize ) -> Result<Option<Number>> {
+        Ok( Some( self.arithmetic.number( record ) ) )
+    }
+    fn initial( &self ) -> Number {
+        self.arithmetic.number( 0 )
+    }
+    fn fold( &self, answer: Number, contribution: Number ) -> Result<Number> {
+        Ok( answer.checked_add( contribution )? )
+    }
+    fn merge( &self, answer: Number, other: Number ) -> Result<Number> {
+        Ok( answer.checked_add( other )? )
+    }
 }
 
 #[cfg(test)]
 mod process_tests {
     use super::*;
 
-    async fn test_process_line( input_output: Vec<(&str, usize)> ) {
+    fn test_process_line( input_output: Vec<(&str, usize)> ) {
         for io in input_output {
-            assert_eq!( process_line( io.0.to_string() ).await, Some(io.1) );
+            assert_eq!( process_line( io.0 ), Some(io.1) );
         }
     }
 
-    #[tokio::test]
-    async fn test_process_line_text() {
+    #[test]
+    fn test_process_line_text() {
         let input_output:Vec<(&str, usize)> = vec![
             ("one", 11),
             ("two", 22),
@@ -73,20 +99,20 @@ mod process_tests {
             ("eightwo", 82),
             ("eighthree", 83),
         ];
-        test_process_line( input_output ).await;
+        test_process_line( input_output );
     }
-    #[tokio::test]
-    async fn test_process_line_digits() {
+    #[test]
+    fn test_process_line_digits() {
         let input_output:Vec<(&str, usize)> = vec![
             ("123", 13),
             ("456", 46),
             ("789", 79),
             ("1235", 15),
         ];
-        test_process_line( input_output ).await;
+        test_process_line( input_output );
     }
-    #[tokio::test]
-    async fn test_process_line_text_digits() {
+    #[test]
+    fn test_process_line_text_digits() {
         let input_output:Vec<(&str, usize)> = vec![
             ("one23", 13),
             ("4five6", 46),
@@ -94,98 +120,20 @@ mod process_tests {
             ("one2twofive", 15),
             ("threeight7", 37),
         ];
-        test_process_line( input_output ).await;
+        test_process_line( input_output );
     }
-    #[tokio::test]
-    async fn test_process_line_no_digits() {
-        assert_eq!( process_line( "azyx".to_string() ).await, None );
-        assert_eq!( process_line( "-?a@$*(@".to_string() ).await, None );
-    }
-}
-
-pub async fn chunker(
-    mut rx: UnboundedReceiver<String>,
-    tx: UnboundedSender<String>
-) -> Result<()> {
-    let mut current_chunk = String::new();
-
-    while let Some( chunk ) = rx.recv().await {
-        current_chunk.push_str( &chunk );
-
-        while let Some( (first,second ) ) = current_chunk.split_once( "\n" ) {
-            if let Err( err ) = tx.send( first.to_string() ) {
-                eprintln!( "Error in sending {err}!" );
-            }
-            current_chunk = second.to_string();
-        }
-    }
-    Ok( () )
-}
-
-#[cfg(test)]
-mod chunker_tests {
-    use super::*;
-    use tokio::sync::mpsc::{ unbounded_channel };
-
-    #[tokio::test]
-    async fn test_long_line() {
-        let input_output:Vec<(&str, Vec<&str>)> = vec![
-            ("abc\n123\ncome-with-me\n", [ "abc", "123", "come-with-me"].to_vec() ),
-            ("neah\nnee\nwee\n", [ "neah", "nee", "wee"].to_vec() ),
-            ("neah\nnee\nwee\nfeh\nleh\njeh\ntee\n", [ "neah", "nee", "wee", "feh", "leh", "jeh", "tee"].to_vec() ),
-            ("neah\nnee\nwee\nfeh\nleh\njeh\ntee", [ "neah", "nee", "wee", "feh", "leh", "jeh"].to_vec() ),
+    #[test]
+    fn test_process_line_typographic() {
+        let input_output:Vec<(&str, usize)> = vec![
+            ("“one” — 2", 12),
+            ("‘4’ then five…", 45),
+            ("½ 7 ¾ nine", 79),
         ];
-
-
-        for ( input, output) in input_output {
-            let (tx,rx) = unbounded_channel::<String>();
-            let (tx_2,mut rx_2) = unbounded_channel::<String>();
-            let chunker_future = tokio::spawn(chunker(rx, tx_2));
-
-            let send = tx.send( input.to_string() );
-            assert!( send.is_ok() );
-
-            for out in output {
-                assert_eq!( rx_2.recv().await, Some( out.to_string() ) );
-            }
-
-            drop( tx );
-            let chunker_result = chunker_future.await;
-            assert!( chunker_result.is_ok() );
-        }
+        test_process_line( input_output );
     }
-}
-
-pub async fn distribute_work( mut rx: UnboundedReceiver<String>) -> Result<()> {
-
-    let mut join_set: JoinSet<Option<usize>> = JoinSet::new();
-
-    while let Some( line ) = rx.recv().await {
-        join_set.spawn( process_line( line ) );
+    #[test]
+    fn test_process_line_no_digits() {
+        assert_eq!( process_line( "azyx" ), None );
+        assert_eq!( process_line( "-?a@$*(@" ), None );
     }
-
-    let mut running_total:usize = 0;
-
-    loop {
-        match join_set.join_next().await {
-            Some( inner ) => {
-                match inner {
-                    Ok( inner ) => {
-                        running_total += inner.unwrap_or(0);
-                    },
-                    Err( err ) => {
-                        eprintln!( "Error processing line {err}!" );
-                    }
-                }
-            },
-            None => {
-                println!( "That's all she wrote..." );
-                break;
-            }
-        }
-    }
-
-    println!( "Result: {running_total}");
-
-    Ok( () )
 }
@@ -0,0 +1,11 @@
+mod structs;
+mod processing;
+
+use aoc_core::Registry;
+use aoc_core::number::Arithmetic;
+
+pub fn register( registry: &mut Registry ) {
+    registry.register( 2, 1, "Cube Conundrum", concat!( env!( "CARGO_MANIFEST_DIR" ), "/input" ), | params | {
+        Ok( processing::PossibleGames::new( structs::Parameters::new( params )?, Arithmetic::from_params( params )? ) )
+    } );
+}
@@ -1,42 +1,50 @@
-use crate::structs::{ Game, Parameters};
-
-use tokio::task::JoinSet;
-
-use std::sync::mpsc::{ Receiver };
-use std::sync::Arc;
+use crate::structs::Parameters;
 
 use anyhow::{ Result };
 
-pub async fn distribute_work( parameters: Arc<Parameters>, rx_line: Receiver<String>) -> Result<()> {
-    let mut join_set = JoinSet::new();
+use day2::Game;
 
-    while let Ok( input_line ) = rx_line.recv() {
-        join_set.spawn( Game::new( parameters.clone(), input_line ) );
-    }
+use aoc_core::Solver;
+use aoc_core::diagnostic::Diagnosed;
+use aoc_core::number::{ Arithmetic, Number };
 
-    let mut running_total:usize = 0;
+pub struct PossibleGames {
+    parameters: Parameters,
+    arithmetic: Arithmetic,
+}
 
-    loop {
-        match join_set.join_next().await {
-            Some( inner ) => {
-                match inner {
-                    Ok( inner ) => {
-                        if let Some( game ) = inner {
-                            running_total += game.id();
-                        }
-                    },
-                    Err( err ) => {
-                        eprintln!( "Something went wrong {err:?}" );
-                    }
-                }
-            }
-            None => {
-                break;
-            }
-        }
+impl PossibleGames {
+    pub fn new( parameters: Parameters, arithmetic: Arithmetic ) -> PossibleGames {
+        PossibleGames { parameters, arithmetic }
     }
+}
 
-    println!( "Total: {running_total}" );
+impl Solver for PossibleGames {
+    type Record = Game;
+    type Contribution = Number;
+    type Answer = Number;
 
-    Ok( () )
+    fn parse( &self, line: &str ) -> Result<Game> {
+        Ok( Game::new( line, self.arithmetic ).map_err( Diagnosed::new )? )
+    }
+    fn describe( &self, game: &Game ) -> Vec<( &'static str, String )> {
+        vec![
+            ( "id", game.id().to_string() ),
+            ( "red", game.red_max().to_string() ),
+            ( "green", game.green_max().to_string() ),
+            ( "blue", game.blue_max().to_string() ),
+        ]
+    }
+    fn contribution( &self, game: Game ) -> Result<Option<Number>> {
+        Ok( self.parameters.is_possible( &game ).then( || self.arithmetic.number( game.id() ) ) )
+    }
+    fn initial( &self ) -> Number {
+        self.arithmetic.number( 0 )
+    }
+    fn fold( &self, answer: Number, contribution: Number ) -> Result<Number> {
+        Ok( answer.checked_add( contribution )? )
+    }
+    fn merge( &self, answer: Number, other: Number ) -> Result<Number> {
+        Ok( answer.checked_add( other )? )
+    }
 }
@@ -0,0 +1,10 @@
+mod processing;
+
+use aoc_core::Registry;
+use aoc_core::number::Arithmetic;
+
+pub fn register( registry: &mut Registry ) {
+    registry.register( 2, 2, "Cube Conundrum", concat!( env!( "CARGO_MANIFEST_DIR" ), "/input" ), | params | {
+        Ok( processing::GamePower { arithmetic: Arithmetic::from_params( params )? } )
+    } );
+}
@@ -1,41 +1,88 @@
-use crate::structs::{ Game };
+use anyhow::{ Result };
 
-use tokio::task::JoinSet;
+use day2::Game;
 
-use std::sync::mpsc::{ Receiver };
+use aoc_core::Solver;
+use aoc_core::diagnostic::Diagnosed;
+use aoc_core::number::{ Arithmetic, Number };
 
-use anyhow::{ Result };
+pub struct GamePower {
+    pub arithmetic: Arithmetic,
+}
 
-pub async fn distribute_work( rx_line: Receiver<String>) -> Result<()> {
-    let mut join_set = JoinSet::new();
+impl Solver for GamePower {
+    type Record = Game;
+    type Contribution = Number;
+    type Answer = Number;
 
-    while let Ok( input_line ) = rx_line.recv() {
-        join_set.spawn( Game::new( input_line ) );
+    fn parse( &self, line: &str ) -> Result<Game> {
+        Ok( Game::new( line, self.arithmetic ).map_err( Diagnosed::new )? )
+    }
+    fn describe( &self, game: &Game ) -> Vec<( &'static str, String )> {
+        vec![
+            ( "id", game.id().to_string() ),
+            ( "red", game.red_max().to_string() ),
+            ( "green", game.green_max().to_string() ),
+            ( "blue", game.blue_max().to_string() ),
+        ]
+    }
+    fn contribution( &self, game: Game ) -> Result<Option<Number>> {
+        let [ red, green, blue ] = [ game.red_max(), game.green_max(), game.blue_max() ].map( Number::clone );
+        Ok( Some( red.checked_mul( blue )?.checked_mul( green )? ) )
+    }
+    fn initial( &self ) -> Number {
+        self.arithmetic.number( 0 )
+    }
+    fn fold( &self, answer: Number, contribution: Number ) -> Result<Number> {
+        Ok( answer.checked_add( contribution )? )
     }
+    fn merge( &self, answer: Number, other: Number ) -> Result<Number> {
+        Ok( answer.checked_add( other )? )
+    }
+}
 
-    let mut running_total:usize = 0;
+#[cfg(test)]
+mod game_tests {
+    use super::*;
 
-    loop {
-        match join_set.join_next().await {
-            Some( inner ) => {
-                match inner {
-                    Ok( inner ) => {
-                        if let Some( game ) = inner {
-                            running_total += game.red_max() * game.blue_max() * game.green_max();
-                        }
+    #[test]
+    fn test_valid_sum() {
+        let input_output:Vec< (Vec<&str>, usize ) > = vec![
+            (
+                vec![
+                  "Game 1: 1 red, 2 blue, 3 red",
+                  "Game 2: 13 red, 14 blue, 15 red",
+                  "Game 3: 1 red, 2 blue, 3 red",
+                  "Game 4: 1 red, 2 blue, 3 red",
+                  "Game 5: 1 red, 2 blue, 3 red",
+                ],
+                0
+            ),
+            (
+                vec![
+                  "Game 1: 13 red, 14 blue, 15 red",
+                  "Game 2: 1 red, 1 blue, 1 red",
+                  "Game 3: 13 red, 14 blue, 15 red",
+                  "Game 4: 13 red, 14 blue, 15 red",
+                  "Game 5: 13 red, 14 blue, 15 red",
+                ],
+                0
+            ),
+        ];
+        
+        for io in input_output {
+            let mut sum = Number::Small( 0 );
+            for io in io.0 {
+                match Game::new( io, Arithmetic::Checked ) {
+                    Ok( game ) => {
+                        let power = game.red_max().clone().checked_mul( game.blue_max().clone() ).unwrap();
+                        sum = sum.checked_add( power.checked_mul( game.green_max().clone() ).unwrap() ).unwrap();
                     },
-                    Err( err ) => {
-                        eprintln!( "Something went wrong {err:?}" );
-                    }
+                    Err( _ ) => continue,
                 }
             }
-            None => {
-                break;
-            }
+
+            assert_eq!( Number::Small( io.1 ), sum );
         }
     }
-
-    println!( "Total: {running_total}" );
-
-    Ok( () )
 }
@@ -0,0 +1,3 @@
+mod structs;
+
+pub use structs::{ Game, GameError };
@@ -0,0 +1,341 @@
+use std::fmt::Display;
+use std::fmt;
+use std::ops::Range;
+
+use anyhow::{ Result };
+
+use aoc_core::diagnostic::{ self, Diagnostic };
+use aoc_core::number::{ Arithmetic, Number, Overflow };
+
+const COLORS:[&str; 3] = [ "red", "green", "blue" ];
+
+/// Why a line is not a game, with the bytes of the line to blame.
+#[derive( Debug, PartialEq )]
+pub enum GameError {
+    /// No `:` between the game id and its sets.
+    MissingColon { span: Range<usize> },
+    /// A game id that is not a number.
+    BadGameId { span: Range<usize> },
+    /// A set without any cubes.
+    EmptySet { span: Range<usize> },
+    /// A cube count that is not a number.
+    BadCount { span: Range<usize> },
+    /// A cube count without a color after it.
+    MissingColor { span: Range<usize> },
+    /// A color other than red, green or blue.
+    UnknownColor { span: Range<usize>, color: String },
+    /// A set with more cubes of one color than the answer can count.
+    TooManyCubes { span: Range<usize>, color: String, overflow: Overflow },
+}
+
+impl Display for GameError {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        match self {
+            GameError::MissingColon { .. } => write!( f, "missing `:` after the game id" ),
+            GameError::BadGameId { .. } => write!( f, "bad game id" ),
+            GameError::EmptySet { .. } => write!( f, "empty set" ),
+            GameError::BadCount { .. } => write!( f, "bad cube count" ),
+            GameError::MissingColor { .. } => write!( f, "missing cube color" ),
+            GameError::UnknownColor { color, .. } => write!( f, "unknown color `{color}`" ),
+            GameError::TooManyCubes { color, .. } => write!( f, "too many {color} cubes" ),
+        }
+    }
+}
+
+impl std::error::Error for GameError {
+    /// The overflow behind too many cubes, which ends the run rather than
+    /// skipping the line.
+    fn source( &self ) -> Option<&( dyn std::error::Error + 'static )> {
+        match self {
+            GameError::TooManyCubes { overflow, .. } => Some( overflow ),
+            _ => None,
+        }
+    }
+}
+
+impl Diagnostic for GameError {
+    fn span( &self ) -> Range<usize> {
+        match self {
+            GameError::MissingColon { span } |
+            GameError::BadGameId { span } |
+            GameError::EmptySet { span } |
+            GameError::BadCount { span } |
+            GameError::MissingColor { span } |
+            GameError::UnknownColor { span, .. } |
+            GameError::TooManyCubes { span, .. } => span.clone(),
+        }
+    }
+    fn help( &self ) -> Option<String> {
+        match self {
+            GameError::UnknownColor { color, .. } => {
+                diagnostic::suggest( color, &COLORS ).map( | color | format!( "did you mean `{color}`?" ) )
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Split `text`, which starts at byte `start` of its line, at every
+/// `separator`, giving each piece with the byte it starts at.
+fn pieces( text: &str, start: usize, separator: char ) -> impl Iterator<Item = ( usize, &str )> {
+    let mut at = start;
+    text.split( separator ).map( move | piece | {
+        let piece_at = at;
+        at += piece.len() + separator.len_utf8();
+        ( piece_at, piece )
+    } )
+}
+
+/// `text`, which starts at byte `at`, without the whitespace around it.
+fn trimmed( at: usize, text: &str ) -> ( usize, &str ) {
+    ( at + text.len() - text.trim_start().len(), text.trim() )
+}
+
+#[derive( Debug, Clone, Copy, PartialEq )]
+enum CubeColor {
+    Red,
+    Green,
+    Blue
+}
+
+impl CubeColor {
+    fn new( text: &str, at: usize ) -> Result<CubeColor, GameError> {
+        match text {
+            "red" => Ok( CubeColor::Red ),
+            "green" => Ok( CubeColor::Green ),
+            "blue" => Ok( CubeColor::Blue ),
+            error => Err( GameError::UnknownColor { span: at..at + error.len(), color: error.to_string() } )
+        }
+    }
+}
+
+impl Display for CubeColor {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        match self {
+            CubeColor::Red =>   write!( f, "Red" ),
+            CubeColor::Green => write!( f, "Green" ),
+            CubeColor::Blue =>  write!( f, "Blue" ),
+        }
+    }
+}
+
+struct Cubes {
+    count: usize,
+    color: CubeColor
+}
+
+impl Cubes {
+    fn new( cubes_text: &str, start: usize ) -> Result<Vec<Cubes>, GameError> {
+        let mut parsed_cubes = Vec::new();
+
+        for ( at, cube ) in pieces( cubes_text, start, ',' ) {
+            let ( at, cube ) = trimmed( at, cube );
+            let ( count, color ) = cube.split_once( ' ' ).unwrap_or( ( cube, "" ) );
+
+            let count_span = at..at + count.len();
+            let count = count.parse().map_err( | _ | GameError::BadCount { span: count_span.clone() } )?;
+
+            let ( color_at, color ) = trimmed( count_span.end + 1, color );
+            if color.is_empty() {
+                return Err( GameError::MissingColor { span: at + cube.len()..at + cube.len() } );
+            }
+
+            let cube = Cubes {
+                count,
+                color: CubeColor::new( color, color_at )?
+            };
+
+            parsed_cubes.push( cube );
+        }
+
+        Ok( parsed_cubes )
+    }
+}
+
+impl Display for Cubes {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        write!( f, "{} {}", self.count, self.color )
+    }
+}
+
+struct Set {
+    cubes: Vec<Cubes>,
+    span: Range<usize>,
+}
+
+impl Set {
+    fn new( set_text: &str, start: usize ) -> Result<Vec<Set>, GameError> {
+        let mut parsed_sets = Vec::new();
+
+        for ( at, set ) in pieces( set_text, start, ';' ) {
+            if set.trim().is_empty() {
+                return Err( GameError::EmptySet { span: at..at + set.len() } );
+            }
+            let ( set_at, trimmed_set ) = trimmed( at, set );
+            let set = Set {
+                cubes: Cubes::new( set, at )?,
+                span: set_at..set_at + trimmed_set.len(),
+            };
+            parsed_sets.push( set )
+        }
+
+        Ok( parsed_sets )
+    }
+    fn cube_color_count( &self, color:CubeColor, arithmetic: Arithmetic ) -> Result<Number, GameError> {
+        let mut color_count = arithmetic.number( 0 );
+        for cube in &self.cubes {
+            if cube.color == color {
+                color_count = color_count.checked_add( arithmetic.number( cube.count ) ).map_err( | overflow | {
+                    GameError::TooManyCubes { span: self.span.clone(), color: color.to_string().to_lowercase(), overflow }
+                } )?;
+            }
+        }
+        Ok( color_count )
+    }
+}
+
+impl Display for Set {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        for cube in &self.cubes {
+            write!( f, "{}, ", cube )?;
+        }
+        write!( f, "; " )
+    }
+}
+
+pub struct Game {
+    id: usize,
+    sets: Vec<Set>,
+    red_max: Number,
+    green_max: Number,
+    blue_max: Number,
+}
+
+impl Game {
+    /// Parse a game, adding up the cubes of each set as `arithmetic`
+    /// does.
+    pub fn new( line: &str, arithmetic: Arithmetic ) -> Result<Game, GameError> {
+        let Some( ( head, games_text ) ) = line.split_once( ':' ) else {
+            return Err( GameError::MissingColon { span: line.len()..line.len() } );
+        };
+
+        let id_at = head.rfind( ' ' ).map_or( 0, | space | space + 1 );
+        let game_id:usize = head[id_at..].parse().map_err( | _ | GameError::BadGameId { span: id_at..head.len() } )?;
+
+        let set = Set::new( games_text, head.len() + 1 )?;
+
+        let color_max = | color:CubeColor | -> Result<Number, GameError> {
+            let mut max = arithmetic.number( 0 );
+            for this_set in &set {
+                max = max.max( this_set.cube_color_count( color, arithmetic )? );
+            }
+            Ok( max )
+        };
+        let red_max   = color_max( CubeColor::Red )?;
+        let green_max = color_max( CubeColor::Green )?;
+        let blue_max  = color_max( CubeColor::Blue )?;
+
+        let game = Game {
+            id: game_id,
+            sets: set,
+            red_max,
+            green_max,
+            blue_max,
+        };
+
+        Ok( game )
+    }
+    pub fn id( &self ) -> usize {
+        self.id
+    }
+    pub fn red_max( &self ) -> &Number {
+        &self.red_max
+    }
+    pub fn green_max( &self ) -> &Number {
+        &self.green_max
+    }
+    pub fn blue_max( &self ) -> &Number {
+        &self.blue_max
+    }
+}
+
+impl Display for Game {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> Result<(), fmt::Error> {
+        write!( f, "Game {}: ", self.id )?;
+        for set in &self.sets {
+            write!( f, "{}", set )?;
+        }
+        write!( f, "\n\tHigh Red:{},\n\tHigh Green:{},\n\tHigh Blue:{}", self.red_max, self.green_max, self.blue_max )?;
+        writeln!( f )
+    }
+}
+
+#[cfg(test)]
+mod game_tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::type_complexity)]
+    fn test_valid_line() {
+        let input_output:Vec< (&str, (usize, usize, usize, usize) ) > = vec![
+            (
+              "Game 1: 1 red, 2 green, 3 blue ",
+                ( 1, 1, 2, 3)
+            ),
+            (
+              "Game 2: 3 red, 8 green, 7 blue ",
+                ( 2, 3, 8, 7)
+            ),
+            (
+              "Game 1010: 1337 red, 420 green, 69 blue ",
+                ( 1010, 1337, 420, 69)
+            ),
+        ];
+        
+        for io in input_output {
+            match Game::new( io.0, Arithmetic::Checked ) {
+                Ok( game ) => {
+                    assert_eq!( io.1.0, game.id() );
+                    assert_eq!( &Number::Small( io.1.1 ), game.red_max() );
+                    assert_eq!( &Number::Small( io.1.2 ), game.green_max() );
+                    assert_eq!( &Number::Small( io.1.3 ), game.blue_max() );
+                },
+                Err( _ ) => continue,
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let input_output:Vec<(&str, GameError)> = vec![
+            ( "Game 1 3 red", GameError::MissingColon { span: 12..12 } ),
+            ( "Game one: 3 red", GameError::BadGameId { span: 5..8 } ),
+            ( "Game 1: 3 red;; 4 blue", GameError::EmptySet { span: 14..14 } ),
+            ( "Game 1: 3 red, x blue", GameError::BadCount { span: 15..16 } ),
+            ( "Game 1: 3 red, 4", GameError::MissingColor { span: 16..16 } ),
+            ( "Game 1: 3 red; 4 gren", GameError::UnknownColor { span: 17..21, color: "gren".to_string() } ),
+        ];
+
+        for ( line, error ) in input_output {
+            assert_eq!( Game::new( line, Arithmetic::Checked ).err(), Some( error ), "{line}" );
+        }
+
+        let error = Game::new( "Game 1: 4 gren", Arithmetic::Checked ).err().unwrap();
+        assert_eq!( error.help(), Some( "did you mean `green`?".to_string() ) );
+    }
+
+    #[test]
+    fn test_too_many_cubes() {
+        const LINE:&str = "Game 1: 18446744073709551615 red, 1 red";
+
+        let error = Game::new( LINE, Arithmetic::Checked ).err().unwrap();
+        assert_eq!( error.to_string(), "too many red cubes" );
+        assert_eq!( error.span(), 8..39 );
+        let overflow = std::error::Error::source( &error ).unwrap().downcast_ref::<Overflow>().unwrap();
+        assert_eq!( overflow.to_string(), "18446744073709551615 + 1 is too large for a usize" );
+
+        let game = Game::new( LINE, Arithmetic::Big ).unwrap();
+        assert_eq!( game.red_max().to_string(), "18446744073709551616" );
+        assert_eq!( game.green_max(), &Number::Small( 0 ) );
+    }
+}